@@ -0,0 +1,16 @@
+use vergen_gitcl::{BuildBuilder, Emitter, GitclBuilder, RustcBuilder};
+
+// Embed build metadata for `tomato-clock version --verbose`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let build = BuildBuilder::default().build_timestamp(true).build()?;
+    let git = GitclBuilder::default().sha(true).dirty(true).build()?;
+    let rustc = RustcBuilder::default().semver(true).host_triple(true).build()?;
+    
+    Emitter::default()
+        .add_instructions(&build)?
+        .add_instructions(&git)?
+        .add_instructions(&rustc)?
+        .emit()?;
+    
+    Ok(())
+}
@@ -4,8 +4,13 @@
 # This script helps to better handle the toggle action between start/pause
 
 CONFIG_DIR="${XDG_CONFIG_HOME:-$HOME/.config}/tomato-clock"
-STATE_FILE="$CONFIG_DIR/state.json"
+STATE_DIR="${XDG_STATE_HOME:-$HOME/.local/state}/tomato-clock"
+RUNTIME_DIR="${XDG_RUNTIME_DIR:+$XDG_RUNTIME_DIR/tomato-clock}"
+RUNTIME_DIR="${RUNTIME_DIR:-$STATE_DIR}"
+STATE_FILE="$STATE_DIR/state.json"
+# Older versions wrote the output next to the config, and still do while that file exists
 WAYBAR_OUTPUT_FILE="$CONFIG_DIR/waybar-output.json"
+[ -f "$WAYBAR_OUTPUT_FILE" ] || WAYBAR_OUTPUT_FILE="$RUNTIME_DIR/waybar-output.json"
 LOCK_FILE="/tmp/tomato-clock-toggle.lock"
 TOMATO_CLOCK_BIN="$HOME/.local/bin/tomato-clock"
 LOGFILE="/tmp/tomato-clock-toggle.log"
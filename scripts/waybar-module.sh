@@ -5,7 +5,12 @@
 
 # Configuration
 CONFIG_DIR="${XDG_CONFIG_HOME:-$HOME/.config}/tomato-clock"
+STATE_DIR="${XDG_STATE_HOME:-$HOME/.local/state}/tomato-clock"
+RUNTIME_DIR="${XDG_RUNTIME_DIR:+$XDG_RUNTIME_DIR/tomato-clock}"
+RUNTIME_DIR="${RUNTIME_DIR:-$STATE_DIR}"
+# Older versions wrote the output next to the config, and still do while that file exists
 OUTPUT_FILE="$CONFIG_DIR/waybar-output.json"
+[ -f "$OUTPUT_FILE" ] || OUTPUT_FILE="$RUNTIME_DIR/waybar-output.json"
 # Use absolute path for tomato-clock
 TOMATO_CLOCK_BIN="$HOME/.local/bin/tomato-clock"
 TOGGLE_SCRIPT="$(dirname "$0")/toggle.sh"
@@ -29,7 +34,7 @@ fi
 # Check if the output file exists
 if [ ! -f "$OUTPUT_FILE" ]; then
     # Create a default output if the file doesn't exist
-    mkdir -p "$CONFIG_DIR"
+    mkdir -p "$(dirname "$OUTPUT_FILE")"
     echo '{"text":"🍅","tooltip":"Tomato Clock is not running","class":"idle"}' > "$OUTPUT_FILE"
     echo "$(date): Created default output file" >> $LOGFILE
 fi
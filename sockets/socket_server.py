@@ -11,9 +11,15 @@ import subprocess
 from pathlib import Path
 
 # 配置
+CONFIG_DIR = os.path.join(os.environ.get("XDG_CONFIG_HOME") or os.path.expanduser("~/.config"), "tomato-clock")
+STATE_DIR = os.path.join(os.environ.get("XDG_STATE_HOME") or os.path.expanduser("~/.local/state"), "tomato-clock")
+RUNTIME_DIR = os.path.join(os.environ["XDG_RUNTIME_DIR"], "tomato-clock") if os.environ.get("XDG_RUNTIME_DIR") else STATE_DIR
 SOCKET_PATH = os.path.expanduser("~/.config/tomato-clock/tomato.sock")
-OUTPUT_FILE = os.path.expanduser("~/.config/tomato-clock/waybar-output.json")
-STATE_FILE = os.path.expanduser("~/.config/tomato-clock/state.json")
+# 旧版本把输出写在配置目录里，该文件存在时仍然使用它
+OUTPUT_FILE = os.path.join(CONFIG_DIR, "waybar-output.json")
+if not os.path.exists(OUTPUT_FILE):
+    OUTPUT_FILE = os.path.join(RUNTIME_DIR, "waybar-output.json")
+STATE_FILE = os.path.join(STATE_DIR, "state.json")
 TOMATO_BIN = os.path.expanduser("~/.local/bin/tomato-clock")
 LOG_FILE = os.path.expanduser("~/.config/tomato-clock/socket_server.log")
 
@@ -0,0 +1,121 @@
+use chrono::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::ResumeMode;
+#[cfg(feature = "bench")]
+use crate::config::{self, Config};
+use crate::persistence;
+use crate::status::Status;
+use crate::timer::{Timer, TimerCommand, TimerEvent};
+use crate::workflow::{Phase, Workflow};
+
+/// Phases of the bench workflow, in minutes
+const BENCH_PHASES: [(&str, u32); 2] = [("Work", 2), ("Break", 1)];
+
+/// Run a short workflow to completion on tokio's paused clock and check that ticks,
+/// phase changes and state saves happen as often as they should. Prints a report
+/// and returns whether everything matched.
+///
+/// Runs on its own runtime, since only a current-thread runtime can pause time.
+/// Persistence, notifications and hooks are off, so nothing on disk is touched.
+#[cfg(feature = "bench")]
+pub fn run() -> bool {
+    config::set(Config {
+        persistence_enabled: false,
+        notification_enabled: false,
+        ..Config::default()
+    });
+    
+    // A runtime can't be started or dropped from inside `main`'s, hence the thread
+    let report = std::thread::spawn(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .map_err(|e| format!("couldn't start a runtime: {}", e))?
+            .block_on(checks())
+    }).join();
+    
+    let checks = match report {
+        Ok(Ok(checks)) => checks,
+        Ok(Err(e)) => {
+            println!("FAIL: {}", e);
+            return false;
+        }
+        Err(_) => {
+            println!("FAIL: the bench panicked");
+            return false;
+        }
+    };
+    
+    let mut passed = true;
+    for (name, actual, expected) in checks {
+        let ok = actual == expected;
+        passed &= ok;
+        println!("{:<12} {:>4}/{:<4} {}", name, actual, expected, if ok { "ok" } else { "MISMATCH" });
+    }
+    
+    println!("{}", if passed { "PASS" } else { "FAIL" });
+    passed
+}
+
+// (check, actual, expected) for each counter. Needs a paused clock to finish quickly
+async fn checks() -> Result<Vec<(&'static str, usize, usize)>, String> {
+    let phases: Vec<Phase> = BENCH_PHASES.iter().map(|(name, minutes)| Phase::new(name, *minutes)).collect();
+    let workflow = Workflow::new("Bench").with_phases(phases.clone()).with_repeatable(false);
+    
+    let timer = Timer::new(ResumeMode::Resume).await;
+    let mut events = timer.subscribe();
+    let saves_before = persistence::update_count();
+    
+    timer.send_command(TimerCommand::Start {
+        workflow: Some(workflow),
+        status: Some(Status::default()),
+        continue_session: false,
+    }).await?;
+    
+    // Time only moves while every task waits, so this runs as fast as the timer allows
+    let mut phase_changes = 0;
+    let mut completions = 0;
+    while completions == 0 {
+        match events.recv().await {
+            Ok(TimerEvent::PhaseChanged { .. }) => phase_changes += 1,
+            Ok(TimerEvent::Completed) => completions += 1,
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Err("the timer stopped sending events".to_string()),
+        }
+    }
+    
+    let info = timer.get_info();
+    let ticks = info.session_elapsed.num_seconds() as usize;
+    let saves = persistence::update_count() - saves_before;
+    
+    // One tick per second of phase; a save on start, every 10s of a phase (except at its
+    // very end), when it completes and when the next phase or the end begins
+    let seconds: Vec<usize> = phases.iter().map(|phase| Duration::minutes(phase.duration as i64).num_seconds() as usize).collect();
+    let expected_ticks = seconds.iter().sum();
+    let expected_saves = 1 + seconds.iter().map(|s| (s - 1) / 10 + 2).sum::<usize>();
+    
+    Ok(vec![
+        ("ticks", ticks, expected_ticks),
+        ("transitions", phase_changes, phases.len() - 1),
+        ("completions", completions, 1),
+        ("state saves", saves, expected_saves),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    use crate::test_support;
+    
+    #[tokio::test(start_paused = true)]
+    async fn short_workflow_ticks_changes_phase_and_saves_on_schedule() {
+        let _sandbox = test_support::sandbox().await;
+        
+        for (name, actual, expected) in checks().await.unwrap() {
+            assert_eq!(actual, expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Duration, Local};
+use std::sync::{Arc, Mutex};
+
+use crate::lock::lock_or_recover;
+
+/// Something that tells the time, swapped in with `set_source`
+pub type TimeSource = Arc<dyn Fn() -> DateTime<Local> + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref SOURCE: Mutex<Option<TimeSource>> = Mutex::new(None);
+}
+
+/// The current time: the real clock unless `set_source` or `set_now` replaced it.
+/// Use this rather than `Local::now()` so demos and tests can control the time
+pub fn now() -> DateTime<Local> {
+    // Clone the source out so it isn't called with the lock held
+    let source = lock_or_recover(&SOURCE).clone();
+    
+    match source {
+        Some(source) => source(),
+        None => Local::now(),
+    }
+}
+
+/// Read the time from `source` from now on
+#[allow(dead_code)]
+pub fn set_source(source: TimeSource) {
+    *lock_or_recover(&SOURCE) = Some(source);
+}
+
+/// Make the clock read `time` right now and keep ticking at the normal rate from there
+pub fn set_now(time: DateTime<Local>) {
+    let offset: Duration = time - Local::now();
+    set_source(Arc::new(move || Local::now() + offset));
+}
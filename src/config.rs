@@ -1,16 +1,144 @@
+use chrono::Weekday;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use toml;
 use dirs;
 
+use crate::lock::lock_or_recover;
+use crate::sound::SoundTheme;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub default_workflow: String,
     pub default_status: String,
+    /// Statuses `cycle-status` steps through, in order; all of them alphabetically when empty
+    #[serde(default)]
+    pub status_order: Vec<String>,
     pub notification_enabled: bool,
+    /// Offer "Start break" / "Skip break" buttons when a break begins, where the notification daemon supports them
+    #[serde(default)]
+    pub notification_actions: bool,
+    /// Treat a persisted `Completed` state as `Idle` when the timer starts up
+    #[serde(default = "default_true")]
+    pub reset_completed_on_startup: bool,
+    /// What the daemon does with a session that was running when it last exited
+    #[serde(default)]
+    pub resume_on_startup: ResumeMode,
+    /// Write timer state to disk; when false everything stays in memory
+    #[serde(default = "default_true")]
+    pub persistence_enabled: bool,
+    /// Record phases cut short by `stop` in history, as with `stop --save-session`
+    #[serde(default)]
+    pub log_interrupted: bool,
+    /// Keep only this many of the newest entries in history.jsonl
+    #[serde(default)]
+    pub history_max_entries: Option<usize>,
+    /// Show logged durations rounded to the nearest minute; history.jsonl keeps exact seconds
+    #[serde(default)]
+    pub history_round_to_minutes: bool,
+    /// Summarize last week's focused time on the first start of each week
+    #[serde(default)]
+    pub weekly_report: bool,
+    /// The day weeks start on for `weekly_report`, e.g. "monday"
+    #[serde(default = "default_weekly_report_day")]
+    pub weekly_report_day: Weekday,
+    /// Remind to take a break after this many minutes of work phases in a row
+    #[serde(default)]
+    pub max_continuous_work_minutes: Option<u32>,
+    /// Notify once per phase when this many seconds are left in it
+    #[serde(default)]
+    pub warn_before_seconds: Option<u32>,
+    /// Play `countdown_tick_sound` each of the last this many seconds of a phase
+    #[serde(default)]
+    pub countdown_tick_seconds: Option<u32>,
+    #[serde(default = "default_countdown_tick_sound")]
+    pub countdown_tick_sound: String,
+    /// Command that plays a sound file given as its only argument
+    #[serde(default = "default_sound_player")]
+    pub sound_player: String,
+    /// Bundled sounds played when a phase ends, unless the phase has its own `sound`
+    #[serde(default)]
+    pub sound_theme: Option<SoundTheme>,
+    /// Speak each phase change through `tts_command`
+    #[serde(default)]
+    pub tts_enabled: bool,
+    /// Text-to-speech command, given the text to say as its argument
+    #[serde(default = "default_tts_command")]
+    pub tts_command: String,
+    /// Shell command that exits 0 while the session is idle or locked; the daemon
+    /// pauses the timer meanwhile. It may print how many seconds it's been idle
+    #[serde(default)]
+    pub idle_detect_command: Option<String>,
+    /// Seconds between runs of `idle_detect_command`
+    #[serde(default = "default_idle_poll_seconds")]
+    pub idle_poll_seconds: u64,
+    /// Minutes left in the phase `previous --keep-progress` goes back to
+    #[serde(default = "default_keep_progress_minutes")]
+    pub keep_progress_minutes: u32,
     pub waybar_integration: WaybarConfig,
+    #[serde(default)]
+    pub notification: NotificationConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Shortcuts for `tomato-clock go <alias>`
+    #[serde(default)]
+    pub aliases: HashMap<String, AliasConfig>,
+}
+
+/// A workflow and status started together by `go`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AliasConfig {
+    pub workflow: String,
+    pub status: String,
+}
+
+/// How a restored `Running` session starts out, see `resume_on_startup`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    /// Keep counting down from where the last save left off
+    #[default]
+    Resume,
+    /// Restore it paused, for the user to resume
+    Pause,
+    /// Stop it, leaving it to `start --continue`
+    Idle,
+}
+
+/// How notifications are shown, see `notification::notifier`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub backend: NotificationBackend,
+    /// Shell command for the `command` backend, with `{summary}` and `{body}`
+    /// replaced by the shell-quoted text
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationBackend {
+    /// Desktop notifications over D-Bus (or the platform's equivalent)
+    #[default]
+    Dbus,
+    /// Run `notification.command` for each notification
+    Command,
+}
+
+/// Shell commands run on timer events, see `hooks::run_hook` for their environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    pub on_start: Option<String>,
+    pub on_phase_change: Option<String>,
+    pub on_pause: Option<String>,
+    pub on_resume: Option<String>,
+    pub on_stop: Option<String>,
+    pub on_complete: Option<String>,
+    /// Runs after `on_start` on the first start of each local day
+    pub on_day_start: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +147,103 @@ pub struct WaybarConfig {
     pub format: String,
     pub socket_path: Option<String>,
     pub click_events: bool,
+    /// Show the default status's icon and name while idle
+    #[serde(default)]
+    pub idle_show_default_status: bool,
+    /// Add a `focus` or `break` class next to `running` based on the phase kind
+    #[serde(default = "default_true")]
+    pub phase_kind_classes: bool,
+    /// Color the bar text with the active phase's color using Pango markup
+    #[serde(default)]
+    pub phase_color_text: bool,
+    /// Color the bar text along a gradient from `gradient_start_color` to
+    /// `gradient_end_color` as the phase progresses; overrides `phase_color_text`
+    #[serde(default)]
+    pub progress_gradient: bool,
+    #[serde(default = "default_gradient_start_color")]
+    pub gradient_start_color: String,
+    #[serde(default = "default_gradient_end_color")]
+    pub gradient_end_color: String,
+    /// Emit `percentage` as the share of the phase left (100 → 0) instead of done
+    #[serde(default)]
+    pub percentage_inverts: bool,
+    /// Show `{remaining}` on the bar as whole minutes ("24m") until the last minute,
+    /// which counts down as `MM:SS`; the tooltip keeps the seconds
+    #[serde(default)]
+    pub compact_minutes: bool,
+    /// Milliseconds between daemon updates of the Waybar output
+    #[serde(default = "default_update_interval_ms")]
+    pub update_interval_ms: u64,
+    /// Tooltip template while running or paused; `None` keeps the built-in one, "" hides it
+    #[serde(default)]
+    pub tooltip_format: Option<String>,
+    /// Pad the bar text with spaces to at least this many columns so the module doesn't jitter
+    #[serde(default)]
+    pub min_width: Option<usize>,
+    /// Second, compact output file for another Waybar module (`~/` is expanded)
+    #[serde(default)]
+    pub mini_output_path: Option<String>,
+    /// Text format for the compact output, with the same placeholders as `format`
+    #[serde(default = "default_mini_format")]
+    pub mini_format: String,
+    /// Characters in the `{progress_bar}` placeholder
+    #[serde(default = "default_progress_bar_width")]
+    pub progress_bar_width: usize,
+    /// The two glyphs `{heartbeat}` alternates between each second while running
+    #[serde(default = "default_heartbeat_glyphs")]
+    pub heartbeat_glyphs: [String; 2],
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_update_interval_ms() -> u64 {
+    500
+}
+
+fn default_mini_format() -> String {
+    "{icon} {remaining}".to_string()
+}
+
+fn default_progress_bar_width() -> usize {
+    10
+}
+
+fn default_gradient_start_color() -> String {
+    "#50fa7b".to_string()
+}
+
+fn default_gradient_end_color() -> String {
+    "#ff5555".to_string()
+}
+
+fn default_heartbeat_glyphs() -> [String; 2] {
+    ["●".to_string(), "○".to_string()]
+}
+
+fn default_countdown_tick_sound() -> String {
+    "/usr/share/sounds/freedesktop/stereo/audio-volume-change.oga".to_string()
+}
+
+fn default_idle_poll_seconds() -> u64 {
+    5
+}
+
+fn default_keep_progress_minutes() -> u32 {
+    1
+}
+
+fn default_weekly_report_day() -> Weekday {
+    Weekday::Mon
+}
+
+fn default_sound_player() -> String {
+    "paplay".to_string()
+}
+
+fn default_tts_command() -> String {
+    "spd-say".to_string()
 }
 
 impl Default for Config {
@@ -26,8 +251,32 @@ impl Default for Config {
         Self {
             default_workflow: "Default Pomodoro".to_string(),
             default_status: "work".to_string(),
+            status_order: Vec::new(),
             notification_enabled: true,
+            notification_actions: false,
+            reset_completed_on_startup: true,
+            resume_on_startup: ResumeMode::default(),
+            persistence_enabled: true,
+            log_interrupted: false,
+            history_max_entries: None,
+            history_round_to_minutes: false,
+            weekly_report: false,
+            weekly_report_day: default_weekly_report_day(),
+            max_continuous_work_minutes: None,
+            warn_before_seconds: None,
+            countdown_tick_seconds: None,
+            countdown_tick_sound: default_countdown_tick_sound(),
+            sound_player: default_sound_player(),
+            sound_theme: None,
+            tts_enabled: false,
+            tts_command: default_tts_command(),
+            idle_detect_command: None,
+            idle_poll_seconds: default_idle_poll_seconds(),
+            keep_progress_minutes: default_keep_progress_minutes(),
             waybar_integration: WaybarConfig::default(),
+            notification: NotificationConfig::default(),
+            hooks: HooksConfig::default(),
+            aliases: HashMap::new(),
         }
     }
 }
@@ -39,22 +288,122 @@ impl Default for WaybarConfig {
             format: "{icon} {status}: {remaining}".to_string(),
             socket_path: None,
             click_events: true,
+            idle_show_default_status: false,
+            phase_kind_classes: true,
+            phase_color_text: false,
+            progress_gradient: false,
+            gradient_start_color: default_gradient_start_color(),
+            gradient_end_color: default_gradient_end_color(),
+            percentage_inverts: false,
+            compact_minutes: false,
+            update_interval_ms: default_update_interval_ms(),
+            tooltip_format: None,
+            min_width: None,
+            mini_output_path: None,
+            mini_format: default_mini_format(),
+            progress_bar_width: default_progress_bar_width(),
+            heartbeat_glyphs: default_heartbeat_glyphs(),
         }
     }
 }
 
 lazy_static::lazy_static! {
     static ref CONFIG: Arc<Mutex<Config>> = Arc::new(Mutex::new(Config::default()));
+    static ref CONFIG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+    // Set when `--config` names a directory, which then holds everything
+    static ref PROFILE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+fn profile_dir() -> Option<PathBuf> {
+    lock_or_recover(&PROFILE_DIR).clone()
 }
 
 pub fn get_config_dir() -> PathBuf {
+    if let Some(dir) = profile_dir() {
+        return dir;
+    }
+    
     let mut config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("./config"));
     config_dir.push("tomato-clock");
     config_dir
 }
 
+/// Where state and history live: `$XDG_STATE_HOME/tomato-clock`, or the config
+/// directory on platforms without a state directory or with a `--config` directory
+pub fn get_state_dir() -> PathBuf {
+    if let Some(dir) = profile_dir() {
+        return dir;
+    }
+    
+    match dirs::state_dir() {
+        Some(mut state_dir) => {
+            state_dir.push("tomato-clock");
+            state_dir
+        }
+        None => get_config_dir(),
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/tomato-clock` for files that only matter while logged in,
+/// falling back to the state directory. A `--config` directory keeps these too,
+/// so a daemon per profile doesn't clash with the others
+pub fn get_runtime_dir() -> PathBuf {
+    if let Some(dir) = profile_dir() {
+        return dir;
+    }
+    
+    match dirs::runtime_dir() {
+        Some(mut runtime_dir) => {
+            runtime_dir.push("tomato-clock");
+            runtime_dir
+        }
+        None => get_state_dir(),
+    }
+}
+
+/// Older versions kept these next to config.toml
+const STATE_FILES: &[&str] = &["state.json", "history.jsonl", "waybar-format"];
+
+/// Move state files left in the config directory by older versions to the state directory.
+/// A file already in the state directory wins, so this only ever runs once per file
+pub fn migrate_state_files() -> Result<Vec<PathBuf>, String> {
+    let config_dir = get_config_dir();
+    let state_dir = get_state_dir();
+    let mut migrated = Vec::new();
+    
+    if config_dir == state_dir {
+        return Ok(migrated);
+    }
+    
+    for name in STATE_FILES {
+        let old_path = config_dir.join(name);
+        let new_path = state_dir.join(name);
+        
+        if !old_path.exists() || new_path.exists() {
+            continue;
+        }
+        
+        fs::create_dir_all(&state_dir)
+            .map_err(|e| format!("Failed to create state directory: {}", e))?;
+        
+        // rename fails across filesystems, so fall back to copying
+        if fs::rename(&old_path, &new_path).is_err() {
+            fs::copy(&old_path, &new_path)
+                .map_err(|e| format!("Failed to copy {}: {}", old_path.display(), e))?;
+            fs::remove_file(&old_path)
+                .map_err(|e| format!("Failed to remove {}: {}", old_path.display(), e))?;
+        }
+        
+        migrated.push(new_path);
+    }
+    
+    Ok(migrated)
+}
+
+/// The config file to load: `custom_path`, or `config.toml` inside it when it's a directory
 pub fn get_config_file_path(custom_path: Option<PathBuf>) -> PathBuf {
     match custom_path {
+        Some(path) if path.is_dir() => path.join("config.toml"),
         Some(path) => path,
         None => {
             let mut path = get_config_dir();
@@ -64,7 +413,21 @@ pub fn get_config_file_path(custom_path: Option<PathBuf>) -> PathBuf {
     }
 }
 
+/// The config file `init` loaded, honoring `--config`
+pub fn get_loaded_config_path() -> PathBuf {
+    lock_or_recover(&CONFIG_PATH).clone().unwrap_or_else(|| get_config_file_path(None))
+}
+
 pub fn init(custom_path: Option<PathBuf>) -> Result<(), String> {
+    // A directory works as a profile: workflows, statuses and state all live in it
+    if let Some(path) = &custom_path {
+        if path.is_dir() {
+            *lock_or_recover(&PROFILE_DIR) = Some(path.clone());
+        } else if path.exists() && !path.is_file() {
+            return Err(format!("{} is neither a file nor a directory", path.display()));
+        }
+    }
+    
     let config_path = get_config_file_path(custom_path);
     
     // Create config directory if it doesn't exist
@@ -91,19 +454,28 @@ pub fn init(custom_path: Option<PathBuf>) -> Result<(), String> {
     };
     
     // Update global config
-    *CONFIG.lock().unwrap() = config;
+    *lock_or_recover(&CONFIG) = config;
+    *lock_or_recover(&CONFIG_PATH) = Some(config_path);
     
     Ok(())
 }
 
 pub fn get() -> Config {
-    CONFIG.lock().unwrap().clone()
+    lock_or_recover(&CONFIG).clone()
+}
+
+/// Replace the configuration for this run only, leaving the file alone
+#[cfg(any(test, feature = "bench"))]
+pub fn set(config: Config) {
+    *lock_or_recover(&CONFIG) = config;
 }
 
-#[allow(dead_code)]
 pub fn update(config: Config) -> Result<(), String> {
-    *CONFIG.lock().unwrap() = config.clone();
-    save_config(&config, None)
+    *lock_or_recover(&CONFIG) = config.clone();
+    
+    // Write back to the file we loaded from, which may be a custom --config path
+    let config_path = lock_or_recover(&CONFIG_PATH).clone();
+    save_config(&config, config_path.as_deref())
 }
 
 pub fn save_config(config: &Config, custom_path: Option<&Path>) -> Result<(), String> {
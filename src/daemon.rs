@@ -0,0 +1,121 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+/// How long `ensure_running` waits for a spawned daemon to take its lock
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a starting daemon keeps trying for its lock; `is_running` holds it for a moment
+const LOCK_RETRY: Duration = Duration::from_millis(500);
+
+/// The file a running daemon keeps locked, holding its pid
+pub fn get_lock_path() -> PathBuf {
+    config::get_runtime_dir().join("daemon.lock")
+}
+
+// Serializes `ensure_running`, so two of them can't both spawn a daemon
+fn get_spawn_lock_path() -> PathBuf {
+    config::get_runtime_dir().join("daemon-spawn.lock")
+}
+
+fn open_lock_file(path: &Path) -> Result<File, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create runtime directory: {}", e))?;
+    }
+    
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// Take the daemon lock for this process, or `None` when another daemon holds it.
+/// The lock lasts as long as the returned file stays open
+pub fn acquire_lock() -> Result<Option<File>, String> {
+    let mut file = open_lock_file(&get_lock_path())?;
+    let started = Instant::now();
+    
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_RETRY => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => return Err(format!("Failed to lock daemon lock file: {}", e)),
+        }
+    }
+    
+    // The pid is only informational, the lock itself is what counts
+    let _ = file.set_len(0).and_then(|_| write!(file, "{}", std::process::id()));
+    Ok(Some(file))
+}
+
+/// Whether a daemon currently holds the lock
+pub fn is_running() -> Result<bool, String> {
+    let file = open_lock_file(&get_lock_path())?;
+    
+    match file.try_lock_shared() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => Err(format!("Failed to check daemon lock file: {}", e)),
+    }
+}
+
+/// Start a detached daemon unless one is running, waiting until it holds its lock.
+/// Returns the new daemon's pid, or `None` when one was already running
+pub fn ensure_running(config_path: Option<&Path>) -> Result<Option<u32>, String> {
+    // Held until the new daemon is up, so a concurrent call sees it running
+    let spawn_lock = open_lock_file(&get_spawn_lock_path())?;
+    spawn_lock
+        .lock()
+        .map_err(|e| format!("Failed to lock daemon spawn lock file: {}", e))?;
+    
+    if is_running()? {
+        return Ok(None);
+    }
+    
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the tomato-clock executable: {}", e))?;
+    let mut command = Command::new(exe);
+    if let Some(config_path) = config_path {
+        command.arg("--config").arg(config_path);
+    }
+    
+    command
+        .arg("daemon")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    
+    // Its own process group, so closing the terminal that started it doesn't stop it
+    #[cfg(unix)]
+    command.process_group(0);
+    
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start the daemon: {}", e))?;
+    
+    let started = Instant::now();
+    while !is_running()? {
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("The daemon exited right away ({}), see the log", status));
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            return Err(format!("The daemon didn't start within {}s", STARTUP_TIMEOUT.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    
+    Ok(Some(child.id()))
+}
@@ -0,0 +1,371 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::clock;
+use crate::config;
+use crate::lock::lock_or_recover;
+use crate::timer::TimerInfo;
+use crate::workflow::PhaseKind;
+
+/// One finished (or cut short) phase, stored as a line of JSON in the history file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub workflow: Option<String>,
+    pub status: Option<String>,
+    pub phase: String,
+    pub kind: PhaseKind,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    /// Time spent running, excluding pauses
+    pub duration_seconds: u64,
+    /// Time spent paused, so `ended_at - started_at` is the active plus paused time
+    #[serde(default)]
+    pub paused_seconds: u64,
+    /// The phase was stopped before it ran out
+    #[serde(default)]
+    pub interrupted: bool,
+}
+
+impl HistoryEntry {
+    /// The logged duration as shown in reports, rounded to the nearest minute
+    /// for `history_round_to_minutes` (24m36s shows as 25m)
+    pub fn display_seconds(&self, round_to_minutes: bool) -> u64 {
+        if round_to_minutes {
+            (self.duration_seconds + 30) / 60 * 60
+        } else {
+            self.duration_seconds
+        }
+    }
+    
+    /// A work phase that ran its course rather than being stopped: what stats count
+    pub fn is_finished_work(&self) -> bool {
+        self.kind == PhaseKind::Work && !self.interrupted
+    }
+    
+    /// Describe the current phase as ending now, or `None` when there's no phase
+    pub fn from_timer(info: &TimerInfo, interrupted: bool) -> Option<Self> {
+        let phase = info.current_phase.as_ref()?;
+        let ended_at = clock::now();
+        let elapsed = info.elapsed_time.max(Duration::zero());
+        let paused = info.phase_paused();
+        
+        Some(Self {
+            workflow: info.current_workflow.as_ref().map(|w| w.name.clone()),
+            status: info.current_status.as_ref().map(|s| s.name.clone()),
+            phase: phase.name.clone(),
+            kind: phase.kind(),
+            started_at: ended_at - elapsed - paused,
+            ended_at,
+            duration_seconds: elapsed.num_seconds() as u64,
+            paused_seconds: paused.num_seconds() as u64,
+            interrupted,
+        })
+    }
+}
+
+pub fn get_history_file_path() -> PathBuf {
+    let mut path = config::get_state_dir();
+    path.push("history.jsonl");
+    path
+}
+
+/// Append `entry` to the history file; a no-op when persistence is disabled
+pub fn record(entry: &HistoryEntry) -> Result<(), String> {
+    if !config::get().persistence_enabled {
+        return Ok(());
+    }
+    
+    let history_path = get_history_file_path();
+    
+    // Create directory if it doesn't exist
+    if let Some(parent) = history_path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create history directory: {}", e))?;
+        }
+    }
+    
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+    
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&history_path)
+        .map_err(|e| format!("Failed to open history file: {}", e))?;
+    
+    writeln!(file, "{}", line)
+        .map_err(|e| format!("Failed to write history file: {}", e))?;
+    
+    match config::get().history_max_entries {
+        Some(max_entries) => trim(max_entries),
+        None => Ok(()),
+    }
+}
+
+// Drop the oldest entries beyond `max_entries`. The kept lines go to a temporary
+// file that replaces the history in one rename, so a crash never leaves it half written
+fn trim(max_entries: usize) -> Result<(), String> {
+    let history_path = get_history_file_path();
+    
+    let content = fs::read_to_string(&history_path)
+        .map_err(|e| format!("Failed to read history file: {}", e))?;
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= max_entries {
+        return Ok(());
+    }
+    
+    let mut kept = lines[lines.len() - max_entries..].join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+    
+    let tmp_path = history_path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, kept)
+        .map_err(|e| format!("Failed to write trimmed history: {}", e))?;
+    fs::rename(&tmp_path, &history_path)
+        .map_err(|e| format!("Failed to replace history file: {}", e))
+}
+
+/// All entries in the history file, oldest first; unreadable lines are skipped
+pub fn load() -> Vec<HistoryEntry> {
+    let file = match fs::File::open(get_history_file_path()) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// The last `limit` entries in the history file, oldest first. Reads backwards from
+/// the end so a long history doesn't have to be parsed in full
+pub fn load_recent(limit: usize) -> Vec<HistoryEntry> {
+    let mut file = match fs::File::open(get_history_file_path()) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    
+    let mut position = match file.seek(SeekFrom::End(0)) {
+        Ok(position) => position,
+        Err(_) => return Vec::new(),
+    };
+    
+    let mut entries = Vec::new();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 4096];
+    
+    while entries.len() < limit && position > 0 {
+        let read = (chunk.len() as u64).min(position) as usize;
+        position -= read as u64;
+        
+        if file.seek(SeekFrom::Start(position)).is_err() || file.read_exact(&mut chunk[..read]).is_err() {
+            break;
+        }
+        buffer.splice(0..0, chunk[..read].iter().copied());
+        
+        // Everything after the first newline is made of whole lines; keep the rest
+        // until the next chunk completes it (or we reach the start of the file)
+        let split = match buffer.iter().position(|&b| b == b'\n') {
+            Some(index) if position > 0 => index + 1,
+            _ if position > 0 => continue,
+            _ => 0,
+        };
+        
+        let complete = buffer.split_off(split);
+        for line in complete.split(|&b| b == b'\n').rev() {
+            if entries.len() == limit {
+                break;
+            }
+            if let Ok(entry) = serde_json::from_slice::<HistoryEntry>(line) {
+                entries.push(entry);
+            }
+        }
+    }
+    
+    entries.reverse();
+    entries
+}
+
+/// Work phases finished on `date` and their minutes, counted like `streak_from`
+pub fn work_summary(entries: &[HistoryEntry], date: NaiveDate) -> (usize, u64) {
+    work_summary_between(entries, date, date)
+}
+
+/// Work phases finished from `first` to `last`, both included, and their minutes
+pub fn work_summary_between(entries: &[HistoryEntry], first: NaiveDate, last: NaiveDate) -> (usize, u64) {
+    let work: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|entry| entry.is_finished_work())
+        .filter(|entry| (first..=last).contains(&entry.ended_at.date_naive()))
+        .collect();
+    
+    let round_to_minutes = config::get().history_round_to_minutes;
+    let minutes = work.iter().map(|entry| entry.display_seconds(round_to_minutes)).sum::<u64>() / 60;
+    (work.len(), minutes)
+}
+
+/// How much one workflow has been used, from `workflow_usage`
+#[derive(Debug, Clone)]
+pub struct WorkflowUsage {
+    pub name: String,
+    /// Finished work phases
+    pub sessions: usize,
+    /// Time spent in those work phases
+    pub seconds: u64,
+    /// When any phase of it last ended
+    pub last_used: Option<DateTime<Local>>,
+}
+
+/// Finished work phases, their time and the last use of each workflow in `names` plus
+/// any other workflow found in `entries`, most used first (unused workflows last, by name)
+pub fn workflow_usage(entries: &[HistoryEntry], names: &[String]) -> Vec<WorkflowUsage> {
+    let round_to_minutes = config::get().history_round_to_minutes;
+    let mut usage: Vec<WorkflowUsage> = names
+        .iter()
+        .map(|name| WorkflowUsage { name: name.clone(), sessions: 0, seconds: 0, last_used: None })
+        .collect();
+    
+    for entry in entries {
+        let Some(name) = &entry.workflow else {
+            continue;
+        };
+        
+        let index = match usage.iter().position(|u| &u.name == name) {
+            Some(index) => index,
+            None => {
+                usage.push(WorkflowUsage { name: name.clone(), sessions: 0, seconds: 0, last_used: None });
+                usage.len() - 1
+            }
+        };
+        
+        // Breaks and stopped phases show the workflow was used, but aren't sessions
+        if entry.is_finished_work() {
+            usage[index].sessions += 1;
+            usage[index].seconds += entry.display_seconds(round_to_minutes);
+        }
+        usage[index].last_used = usage[index].last_used.max(Some(entry.ended_at));
+    }
+    
+    usage.sort_by(|a, b| b.sessions.cmp(&a.sessions).then(b.seconds.cmp(&a.seconds)).then(a.name.cmp(&b.name)));
+    usage
+}
+
+/// Consecutive local days, up to `today`, with at least one finished work phase.
+/// A streak that reached yesterday still counts before today's first one
+pub fn streak_from(entries: &[HistoryEntry], today: NaiveDate) -> u32 {
+    let days: HashSet<NaiveDate> = entries
+        .iter()
+        .filter(|entry| entry.is_finished_work())
+        .map(|entry| entry.ended_at.date_naive())
+        .collect();
+    
+    let mut day = if days.contains(&today) { Some(today) } else { today.pred_opt() };
+    let mut streak = 0;
+    while let Some(date) = day.filter(|date| days.contains(date)) {
+        streak += 1;
+        day = date.pred_opt();
+    }
+    streak
+}
+
+// The history file's size and modification time plus the day a streak was counted
+// on; the streak only needs counting again once one of them changes
+type StreakKey = (NaiveDate, u64, Option<SystemTime>);
+
+static STREAK_CACHE: Mutex<Option<(StreakKey, u32)>> = Mutex::new(None);
+
+/// The current streak from the history file, see `streak_from`. Cached, so the
+/// daemon only reads the history again after a phase is recorded or the day changes
+pub fn streak() -> u32 {
+    let today = clock::now().date_naive();
+    let metadata = fs::metadata(get_history_file_path()).ok();
+    let key = (
+        today,
+        metadata.as_ref().map_or(0, |m| m.len()),
+        metadata.and_then(|m| m.modified().ok()),
+    );
+    
+    let mut cache = lock_or_recover(&STREAK_CACHE);
+    if let Some((cached_key, streak)) = *cache {
+        if cached_key == key {
+            return streak;
+        }
+    }
+    
+    let streak = streak_from(&load(), today);
+    *cache = Some((key, streak));
+    streak
+}
+
+/// The first day of the week `date` falls in, for weeks starting on `first_day`
+pub fn week_start(date: NaiveDate, first_day: Weekday) -> NaiveDate {
+    let days_into_week = date.weekday().days_since(first_day);
+    date - Duration::days(days_into_week as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    // A finished phase of `minutes` and `seconds` that ended at 10:00 on `day` of March 2026
+    fn entry(phase: &str, kind: PhaseKind, day: u32, minutes: u64, seconds: u64) -> HistoryEntry {
+        let ended_at = Local.with_ymd_and_hms(2026, 3, day, 10, 0, 0).unwrap();
+        let duration_seconds = minutes * 60 + seconds;
+        HistoryEntry {
+            workflow: Some("Default Pomodoro".to_string()),
+            status: None,
+            phase: phase.to_string(),
+            kind,
+            started_at: ended_at - Duration::seconds(duration_seconds as i64),
+            ended_at,
+            duration_seconds,
+            paused_seconds: 0,
+            interrupted: false,
+        }
+    }
+    
+    #[test]
+    fn rounded_durations_go_to_the_nearest_minute() {
+        assert_eq!(entry("Work", PhaseKind::Work, 2, 24, 36).display_seconds(true), 25 * 60);
+        assert_eq!(entry("Work", PhaseKind::Work, 2, 24, 29).display_seconds(true), 24 * 60);
+        assert_eq!(entry("Work", PhaseKind::Work, 2, 24, 36).display_seconds(false), 24 * 60 + 36);
+    }
+    
+    #[test]
+    fn workflow_usage_counts_only_finished_work_phases() {
+        let mut stopped = entry("Work 2", PhaseKind::Work, 3, 10, 0);
+        stopped.interrupted = true;
+        let entries = [
+            entry("Work", PhaseKind::Work, 2, 25, 0),
+            entry("Break", PhaseKind::Break, 2, 5, 0),
+            stopped,
+        ];
+        
+        let usage = workflow_usage(&entries, &["Default Pomodoro".to_string()]);
+        assert_eq!((usage[0].sessions, usage[0].seconds), (1, 25 * 60));
+        assert_eq!(usage[0].last_used, Some(entries[2].ended_at));
+    }
+    
+    #[test]
+    fn stopped_work_counts_towards_neither_summary_nor_streak() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 3).unwrap();
+        let mut stopped = entry("Work", PhaseKind::Work, 3, 10, 0);
+        stopped.interrupted = true;
+        let entries = [entry("Work", PhaseKind::Work, 2, 25, 0), stopped];
+        
+        assert_eq!(work_summary(&entries, today), (0, 0));
+        assert_eq!(work_summary(&entries, today.pred_opt().unwrap()), (1, 25));
+        assert_eq!(streak_from(&entries, today), 1);
+    }
+}
@@ -0,0 +1,94 @@
+use log::{error, warn};
+use tokio::process::Command;
+
+use crate::clock;
+use crate::config;
+use crate::timer::TimerInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Started,
+    PhaseChanged,
+    Paused,
+    Resumed,
+    Stopped,
+    Completed,
+    DayStarted,
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            HookEvent::Started => "started",
+            HookEvent::PhaseChanged => "phase_changed",
+            HookEvent::Paused => "paused",
+            HookEvent::Resumed => "resumed",
+            HookEvent::Stopped => "stopped",
+            HookEvent::Completed => "completed",
+            HookEvent::DayStarted => "day_started",
+        }
+    }
+}
+
+/// Run the configured hook for `event`, if any, without waiting for it to finish.
+///
+/// The command runs through `sh -c` with the timer state in the environment:
+/// `TOMATO_EVENT`, `TOMATO_PHASE`, `TOMATO_STATUS`, `TOMATO_WORKFLOW`,
+/// `TOMATO_REMAINING_SECONDS` and `TOMATO_END_TIME` (RFC 3339, local time).
+/// Variables without a value (e.g. no phase after stopping) are set to "".
+pub fn run_hook(event: HookEvent, info: &TimerInfo) {
+    let hooks = config::get().hooks;
+    let command = match event {
+        HookEvent::Started => hooks.on_start,
+        HookEvent::PhaseChanged => hooks.on_phase_change,
+        HookEvent::Paused => hooks.on_pause,
+        HookEvent::Resumed => hooks.on_resume,
+        HookEvent::Stopped => hooks.on_stop,
+        HookEvent::Completed => hooks.on_complete,
+        HookEvent::DayStarted => hooks.on_day_start,
+    };
+
+    let command = match command {
+        Some(command) if !command.trim().is_empty() => command,
+        _ => return,
+    };
+
+    let phase = info.current_phase.as_ref().map(|p| p.name.clone()).unwrap_or_default();
+    let status = info.current_status.as_ref().map(|s| s.name.clone()).unwrap_or_default();
+    let workflow = info.current_workflow.as_ref().map(|w| w.name.clone()).unwrap_or_default();
+    let remaining_seconds = info
+        .time_remaining
+        .map(|remaining| remaining.num_seconds().max(0).to_string())
+        .unwrap_or_default();
+    let end_time = info
+        .time_remaining
+        .map(|remaining| (clock::now() + remaining).to_rfc3339())
+        .unwrap_or_default();
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .env("TOMATO_EVENT", event.name())
+        .env("TOMATO_PHASE", phase)
+        .env("TOMATO_STATUS", status)
+        .env("TOMATO_WORKFLOW", workflow)
+        .env("TOMATO_REMAINING_SECONDS", remaining_seconds)
+        .env("TOMATO_END_TIME", end_time)
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            // Reap the child in the background so hooks never block the timer
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => {
+                        warn!("Hook for '{}' exited with {}", event.name(), status)
+                    }
+                    Err(e) => error!("Failed to wait for hook: {}", e),
+                    _ => {}
+                }
+            });
+        }
+        Err(e) => error!("Failed to run hook for '{}': {}", event.name(), e),
+    }
+}
@@ -0,0 +1,142 @@
+use log::{error, info, warn};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::config;
+use crate::status::StatusManager;
+use crate::timer::{Timer, TimerCommand};
+use crate::workflow::WorkflowManager;
+
+/// Address `daemon --http` binds to when no address is given
+pub const DEFAULT_HTTP_ADDR: &str = "127.0.0.1:7878";
+
+// Requests are a request line plus a few headers; anything bigger is rejected
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Serve the timer over HTTP: `GET /status` returns the timer info as JSON,
+/// `POST /start`, `/pause`, `/resume`, `/stop` and `/skip` control it.
+pub async fn serve(addr: SocketAddr, timer: Arc<AsyncMutex<Timer>>) -> Result<(), String> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to bind HTTP server to {}: {}", addr, e))?;
+    
+    if !addr.ip().is_loopback() {
+        warn!("HTTP server is listening on {}, which is reachable from other machines", addr);
+    }
+    info!("HTTP server listening on http://{}", addr);
+    
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("Failed to accept HTTP connection: {}", e);
+                continue;
+            }
+        };
+        
+        let timer = Arc::clone(&timer);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, timer).await {
+                warn!("HTTP request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, timer: Arc<AsyncMutex<Timer>>) -> Result<(), String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    
+    // Only the request line matters, so read until the end of the headers
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return write_response(&mut stream, "413 Payload Too Large", &error_body("Request too large")).await;
+        }
+    }
+    
+    let request = String::from_utf8_lossy(&buffer);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+    
+    let (status, body) = route(method, path, &timer).await;
+    write_response(&mut stream, status, &body).await
+}
+
+async fn route(method: &str, path: &str, timer: &Arc<AsyncMutex<Timer>>) -> (&'static str, String) {
+    let command = match (method, path) {
+        ("GET", "/status") => None,
+        ("POST", "/start") => match default_start_command() {
+            Ok(command) => Some(command),
+            Err(e) => return ("500 Internal Server Error", error_body(&e)),
+        },
+        ("POST", "/pause") => Some(TimerCommand::Pause),
+        ("POST", "/resume") => Some(TimerCommand::Resume),
+        ("POST", "/stop") => Some(TimerCommand::Stop { save_session: false }),
+        ("POST", "/skip") => Some(TimerCommand::Skip { silent: false }),
+        (_, "/status" | "/start" | "/pause" | "/resume" | "/stop" | "/skip") => {
+            return ("405 Method Not Allowed", error_body("Method not allowed"));
+        }
+        _ => return ("404 Not Found", error_body("Not found")),
+    };
+    
+    let timer_lock = timer.lock().await;
+    
+    if let Some(command) = command {
+        if let Err(e) = timer_lock.send_command(command).await {
+            return ("500 Internal Server Error", error_body(e));
+        }
+        
+        // Give the timer task a moment so the response reflects the command
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+    
+    match serde_json::to_string(&timer_lock.get_info()) {
+        Ok(body) => ("200 OK", body),
+        Err(e) => ("500 Internal Server Error", error_body(&format!("Failed to serialize timer info: {}", e))),
+    }
+}
+
+// `POST /start` uses the configured default workflow and status
+fn default_start_command() -> Result<TimerCommand, String> {
+    let config = config::get();
+    
+    let workflow = WorkflowManager::new().get_workflow(&config.default_workflow)
+        .ok_or_else(|| format!("Default workflow '{}' not found", config.default_workflow))?;
+    let status = StatusManager::new().get_status(&config.default_status)
+        .ok_or_else(|| format!("Default status '{}' not found", config.default_status))?;
+    
+    Ok(TimerCommand::Start {
+        workflow: Some(workflow),
+        status: Some(status),
+        continue_session: false,
+    })
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), String> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    
+    stream.write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))
+}
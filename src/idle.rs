@@ -0,0 +1,77 @@
+use chrono::Duration;
+use log::{info, warn};
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::timer::{Timer, TimerCommand, TimerState};
+
+/// Poll `command` and pause the timer while the session is idle or locked.
+///
+/// The command runs through `sh -c` and exits 0 while idle. If it prints a number,
+/// that's how many seconds the session has been idle, and that time is taken back
+/// from the phase so the countdown doesn't include it. Only a pause made here is
+/// resumed here: a timer paused by hand stays paused after unlocking.
+pub async fn watch(timer: Arc<AsyncMutex<Timer>>, command: String, poll_seconds: u64) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(poll_seconds.max(1)));
+    let mut paused_by_us = false;
+    
+    loop {
+        interval.tick().await;
+        
+        let idle_for = match check_idle(&command).await {
+            Ok(idle_for) => idle_for,
+            Err(e) => {
+                warn!("Idle detection command failed: {}", e);
+                continue;
+            }
+        };
+        
+        let timer_lock = timer.lock().await;
+        let state = timer_lock.get_info().state;
+        
+        let command = match (idle_for, state) {
+            (Some(idle_for), TimerState::Running) => {
+                info!("Session is idle, pausing the timer");
+                paused_by_us = true;
+                TimerCommand::PauseIdle { idle_for }
+            }
+            (None, TimerState::Paused) if paused_by_us => {
+                info!("Session is active again, resuming the timer");
+                paused_by_us = false;
+                TimerCommand::Resume
+            }
+            (None, _) => {
+                paused_by_us = false;
+                continue;
+            }
+            _ => continue,
+        };
+        
+        if let Err(e) = timer_lock.send_command(command).await {
+            warn!("Failed to send idle command to the timer: {}", e);
+        }
+    }
+}
+
+// `Some(idle time)` while idle; the time is zero when the command doesn't print one
+async fn check_idle(command: &str) -> Result<Option<Duration>, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+    
+    if !output.status.success() {
+        return Ok(None);
+    }
+    
+    let idle_seconds = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .unwrap_or(0.0)
+        .max(0.0);
+    
+    Ok(Some(Duration::seconds(idle_seconds as i64)))
+}
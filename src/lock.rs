@@ -0,0 +1,28 @@
+use log::warn;
+use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Lock a mutex, recovering the guard if another thread panicked while holding it.
+/// All shared state in this crate is plain data that stays usable after a panic,
+/// so one bad thread shouldn't make every later access panic too.
+pub fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        warn!("Recovering from a poisoned lock");
+        poisoned.into_inner()
+    })
+}
+
+/// Read-lock an `RwLock`, recovering from poisoning like `lock_or_recover`
+pub fn read_or_recover<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| {
+        warn!("Recovering from a poisoned lock");
+        poisoned.into_inner()
+    })
+}
+
+/// Write-lock an `RwLock`, recovering from poisoning like `lock_or_recover`
+pub fn write_or_recover<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| {
+        warn!("Recovering from a poisoned lock");
+        poisoned.into_inner()
+    })
+}
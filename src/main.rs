@@ -1,23 +1,50 @@
-use clap::{Parser, Subcommand};
-use log::{error, info, LevelFilter};
-use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
+use chrono::{DateTime, Local, NaiveTime};
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{error, info, warn, LevelFilter};
+use simplelog::{ColorChoice, Config, SimpleLogger, TermLogger, TerminalMode, WriteLogger};
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::signal::ctrl_c;
 use std::time::Duration as StdDuration;
 
+#[cfg(any(test, feature = "bench"))]
+mod bench;
+mod clock;
 mod config;
+mod daemon;
+mod history;
+mod hooks;
+mod idle;
+#[cfg(feature = "http")]
+mod http;
+mod lock;
+mod notification;
 mod persistence;
+mod recorder;
+mod schedule;
+mod setup;
+mod sound;
 mod status;
+#[cfg(test)]
+mod test_support;
 mod timer;
+mod tts;
 mod waybar;
 mod workflow;
 
 use crate::status::StatusManager;
-use crate::timer::{Timer, TimerCommand, TimerState};
-use crate::waybar::update_waybar_output;
-use crate::workflow::{Workflow, WorkflowManager};
+use crate::timer::{Timer, TimerCommand, TimerEvent, TimerInfo, TimerState};
+use crate::waybar::{update_waybar_output, WaybarOutput};
+use crate::workflow::{Phase, Workflow, WorkflowManager};
+
+/// Fastest update cadence the daemon accepts, to keep it from spinning
+const MIN_UPDATE_INTERVAL_MS: u64 = 100;
+
+/// Upper bound for the backoff after repeated Waybar write failures
+const WAYBAR_RETRY_MAX_DELAY_MS: u64 = 8000;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -25,11 +52,73 @@ struct Cli {
     /// Sets a custom config file
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
+    
+    /// When to color output; `auto` colors a terminal unless NO_COLOR is set
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto, global = true)]
+    color: ColorMode,
+
+    /// Start the clock at this RFC 3339 time instead of the real one, for demos and tests
+    #[arg(long, value_name = "TIME", value_parser = parse_now, global = true)]
+    now: Option<DateTime<Local>>,
+
+    /// Speak phase changes through `tts_command`, as if `tts_enabled` were set
+    #[arg(long, global = true)]
+    announce: bool,
+
+    /// Play this bundled theme when phases end, overriding `sound_theme`
+    #[arg(long, value_enum, value_name = "THEME", global = true)]
+    sound_theme: Option<sound::SoundTheme>,
 
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+fn parse_clock_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|e| format!("expected a time like 14:30: {}", e))
+}
+
+fn parse_now(value: &str) -> Result<DateTime<Local>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Local))
+        .map_err(|e| format!("expected a time like 2024-01-01T09:00:00+01:00: {}", e))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    // Decide `auto` here: the logger's own detection ignores NO_COLOR and pipes
+    fn color_choice(self) -> ColorChoice {
+        match self {
+            ColorMode::Always => ColorChoice::Always,
+            ColorMode::Never => ColorChoice::Never,
+            ColorMode::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                if no_color || !std::io::stdout().is_terminal() {
+                    ColorChoice::Never
+                } else {
+                    ColorChoice::Auto
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InfoField {
+    Remaining,
+    Phase,
+    Status,
+    State,
+    Percentage,
+    Elapsed,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start the timer with the current or specified workflow
@@ -41,63 +130,332 @@ enum Commands {
         /// Specify the status to use
         #[arg(short, long)]
         status: Option<String>,
+        
+        /// Resume a paused timer and leave a running one alone instead of restarting it
+        #[arg(long)]
+        resume_if_exists: bool,
+        
+        /// Pick up where `stop` left off if the last stopped session used this workflow
+        #[arg(long = "continue")]
+        continue_session: bool,
+    },
+    /// Run a workflow in the foreground, printing each phase change, until it completes or Ctrl+C
+    Run {
+        /// Workflow to run instead of the default one
+        workflow: Option<String>,
+        
+        /// Specify the status to use
+        #[arg(short, long)]
+        status: Option<String>,
+    },
+    /// Start the workflow and status saved under an alias in the config
+    Go {
+        /// Name of the alias
+        alias: String,
     },
     /// Stop the timer
-    Stop,
+    Stop {
+        /// Record the unfinished phase in history as interrupted
+        #[arg(long)]
+        save_session: bool,
+    },
     /// Pause the timer
-    Pause,
+    Pause {
+        /// Pause every running timer; there is a single timer, so this is the same as `pause`
+        #[arg(long)]
+        all: bool,
+    },
     /// Resume the timer
-    Resume,
+    Resume {
+        /// Resume every paused timer; there is a single timer, so this is the same as `resume`
+        #[arg(long)]
+        all: bool,
+    },
     /// Skip the current phase
-    Skip,
-    /// Set the current status
+    Skip {
+        /// Don't notify about the phase this skips to
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Count one rep of a rep-based phase, moving on after the last
+    Rep,
+    /// Go back to the previous phase, e.g. after skipping by accident
+    Previous {
+        /// Resume the phase with `keep_progress_minutes` left instead of its full duration
+        #[arg(long)]
+        keep_progress: bool,
+        /// Don't notify about the phase this goes back to
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Switch to the next status in `status_order`, wrapping around
+    CycleStatus {
+        /// Go to the previous status instead
+        #[arg(long)]
+        reverse: bool,
+    },
+    /// Set the current status or manage statuses
+    #[command(args_conflicts_with_subcommands = true)]
     Status {
         /// The status to set (e.g., work, study, chilling)
-        name: String,
+        name: Option<String>,
+        
+        /// Restart the workflow from its first phase instead of relabeling the current session
+        #[arg(long, requires = "name")]
+        restart: bool,
+
+        #[command(subcommand)]
+        action: Option<StatusCommands>,
     },
     /// Manage workflows
     Workflow {
         #[command(subcommand)]
         action: WorkflowCommands,
     },
+    /// Run a short workflow on a simulated clock and check the timer's bookkeeping (for maintainers)
+    #[cfg(feature = "bench")]
+    #[command(name = "_bench", hide = true)]
+    Bench,
+    /// Start a detached daemon unless one is already running, e.g. before other commands in a keybind
+    EnsureDaemon,
     /// Run as a daemon for Waybar integration
-    Daemon,
+    Daemon {
+        /// Milliseconds between Waybar updates (overrides update_interval_ms in the config)
+        #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(MIN_UPDATE_INTERVAL_MS..))]
+        interval: Option<u64>,
+        
+        /// Serve the timer as JSON over HTTP (GET /status, POST /start, /pause, /resume, /stop, /skip)
+        #[cfg(feature = "http")]
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = http::DEFAULT_HTTP_ADDR)]
+        http: Option<std::net::SocketAddr>,
+        
+        /// Append every timer event with its time to this JSONL file, for bug reports
+        #[arg(long, value_name = "PATH")]
+        record_events: Option<PathBuf>,
+    },
     /// Display the current timer information
-    Info,
+    Info {
+        /// Print only this value, without a newline, for shell prompts
+        #[arg(long, value_enum)]
+        field: Option<InfoField>,
+        
+        /// Print stable `key=value` lines for scripts (see the README for the keys)
+        #[arg(long, conflicts_with = "field")]
+        porcelain: bool,
+    },
+    /// Show how long until the next break phase begins
+    NextBreak,
+    /// Have the daemon pause the timer at a clock time, e.g. before a meeting
+    SchedulePause {
+        /// Local time to pause at; tomorrow when it has already passed today
+        #[arg(long, value_name = "HH:MM", value_parser = parse_clock_time, required_unless_present = "cancel")]
+        at: Option<NaiveTime>,
+        
+        /// Drop the scheduled pause
+        #[arg(long, conflicts_with = "at")]
+        cancel: bool,
+    },
+    /// Change the Waybar text format; the running daemon picks it up on its next update
+    SetFormat {
+        /// The new format, e.g. "{icon} {remaining}"
+        #[arg(required_unless_present = "clear")]
+        format: Option<String>,
+        
+        /// Also save it as `format` in the config file
+        #[arg(long, conflicts_with = "clear")]
+        save: bool,
+        
+        /// Go back to the format from the config file
+        #[arg(long)]
+        clear: bool,
+    },
+    /// List the most recent phases from the history
+    Sessions {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show today's and this week's work phases and the current streak from the history
+    Stats,
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Interactively set up the configuration
+    Init,
+    /// Restore the default state and workflows, backing up the old files
+    ResetAll {
+        /// Leave the config file alone
+        #[arg(long)]
+        keep_config: bool,
+        
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        
+        /// List the files that would be reset without touching them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the version, with build details for bug reports
+    Version {
+        /// Include the git commit, build date and rustc version
+        #[arg(short, long)]
+        verbose: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum WorkflowCommands {
     /// List all available workflows
-    List,
+    List {
+        /// Print only the names, one per line, for scripts and pickers
+        #[arg(long)]
+        names_only: bool,
+    },
     /// Add a new workflow
     Add {
         /// Name of the workflow
         name: String,
         /// Phases in format "name:duration_mins,name:duration_mins,..."
-        phases: String,
+        #[arg(required_unless_present_any = ["from_template", "phases_file"])]
+        phases: Option<String>,
+        /// Copy the phases of a built-in template (see `workflow templates`) to edit later
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "phases")]
+        from_template: Option<String>,
+        /// Read phases from a file, one "name:duration[:icon:color]" per line; # starts a comment line
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["phases", "from_template"])]
+        phases_file: Option<PathBuf>,
+        /// Write the workflow to this TOML file instead of workflows.json
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// Icon shown in `workflow list` and for phases without their own
+        #[arg(long)]
+        icon: Option<String>,
     },
+    /// List the built-in templates for `workflow add --from-template`
+    Templates,
     /// Remove a workflow
     Remove {
         /// Name of the workflow to remove
         name: String,
+        
+        /// Show what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Set the workflow used when starting without --workflow
+    SetDefault {
+        /// Name of the workflow
+        name: String,
+    },
+    /// Show how often a workflow was used according to the history, or rank all of them
+    Stats {
+        /// Name of the workflow; all workflows, most used first, when omitted
+        name: Option<String>,
+    },
+    /// Check a workflow for zero durations, duplicate or unreachable phases
+    Validate {
+        /// Name of the workflow to check
+        name: String,
+    },
+    /// Import all workflow files (*.toml, *.json) from a directory
+    Import {
+        /// Directory to import from
+        #[arg(long, value_name = "DIR")]
+        dir: PathBuf,
     },
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logger
-    TermLogger::init(
-        LevelFilter::Info,
-        Config::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Show which files are in use, after --config is applied
+    Path,
+}
+
+#[derive(Subcommand)]
+enum StatusCommands {
+    /// List all available statuses
+    List {
+        /// Print only the names, one per line, for scripts and pickers
+        #[arg(long)]
+        names_only: bool,
+    },
+    /// Show every field of one status
+    Show {
+        /// Name of the status
+        name: String,
+    },
+    /// Set the status used when starting without --status
+    SetDefault {
+        /// Name of the status
+        name: String,
+    },
+}
+
+// Whether the command's stdout is meant for other programs, so info logs must stay out of it
+fn wants_plain_output(cli: &Cli) -> bool {
+    matches!(
+        &cli.command,
+        Some(Commands::Workflow { action: WorkflowCommands::List { names_only: true } })
+            | Some(Commands::Status { action: Some(StatusCommands::List { names_only: true }), .. })
+            | Some(Commands::Info { field: Some(_), .. })
+            | Some(Commands::Info { porcelain: true, .. })
+            | Some(Commands::Run { .. })
     )
-    .unwrap_or_else(|e| {
-        eprintln!("Failed to initialize logger: {}", e);
-    });
+}
+
+// Log to the terminal, or when that logger can't start, to plain stderr on a TTY and
+// to a file in the state directory otherwise, so a detached daemon's logs aren't lost
+fn init_logger(log_level: LevelFilter, color_choice: ColorChoice) {
+    let Err(e) = TermLogger::init(log_level, Config::default(), TerminalMode::Mixed, color_choice) else {
+        return;
+    };
+    
+    if !std::io::stderr().is_terminal() {
+        let log_path = config::get_state_dir().join("tomato-clock.log");
+        let log_file = std::fs::create_dir_all(config::get_state_dir())
+            .and_then(|_| std::fs::OpenOptions::new().create(true).append(true).open(&log_path));
+        
+        match log_file {
+            Ok(file) => {
+                if WriteLogger::init(log_level, Config::default(), file).is_ok() {
+                    return;
+                }
+            }
+            Err(file_error) => eprintln!("Failed to open log file {}: {}", log_path.display(), file_error),
+        }
+    }
+    
+    if let Err(simple_error) = SimpleLogger::init(log_level, Config::default()) {
+        eprintln!("Failed to initialize logger: {} ({})", e, simple_error);
+    }
+}
 
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    
+    // Initialize logger
+    let log_level = if wants_plain_output(&cli) { LevelFilter::Warn } else { LevelFilter::Info };
+    init_logger(log_level, cli.color.color_choice());
+    
+    if let Some(now) = cli.now {
+        clock::set_now(now);
+        info!("Clock set to {}", now.to_rfc3339());
+    }
+    
+    if cli.announce {
+        tts::enable();
+    }
+    
+    if let Some(theme) = cli.sound_theme {
+        sound::set_theme(theme);
+    }
+    
+    // Remember whether this is a first run before init writes the default config
+    let first_run = !config::get_config_file_path(cli.config.clone()).exists();
 
     // Initialize configuration
     match config::init(cli.config.clone()) {
@@ -108,6 +466,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Runs on its own config and state, so it must start before the real state is loaded
+    #[cfg(feature = "bench")]
+    if matches!(cli.command, Some(Commands::Bench)) {
+        std::process::exit(if bench::run() { 0 } else { 1 });
+    }
+
+    // Older versions kept state and history in the config directory
+    match config::migrate_state_files() {
+        Ok(migrated) => {
+            for path in migrated {
+                info!("Moved {} out of the config directory", path.display());
+            }
+        }
+        Err(e) => warn!("Failed to move state files to the state directory: {}", e),
+    }
+
     // Initialize persistence
     match persistence::init() {
         Ok(_) => info!("Persistence initialized"),
@@ -121,8 +495,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let status_manager = StatusManager::new();
     let workflow_manager = WorkflowManager::new();
     
-    // Create timer
-    let timer = Arc::new(AsyncMutex::new(Timer::new().await));
+    // Held until the process exits; `ensure-daemon` checks for it. Taken before the
+    // timer exists, so a daemon that can't start doesn't touch the restored session
+    let is_daemon = matches!(cli.command, Some(Commands::Daemon { .. }));
+    let _daemon_lock = if is_daemon {
+        match daemon::acquire_lock() {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                error!("A daemon is already running (see {})", daemon::get_lock_path().display());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    
+    // Create timer; only the daemon acts on `resume_on_startup`
+    let on_startup = if is_daemon { config::get().resume_on_startup } else { config::ResumeMode::Resume };
+    let timer = Arc::new(AsyncMutex::new(Timer::new(on_startup).await));
 
     // Create global lock to ensure only one command runs at a time
     // Keeping this for future use, but marking as unused to suppress warnings
@@ -130,9 +524,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Process commands
     match cli.command {
-        Some(Commands::Start { workflow, status }) => {
+        Some(Commands::Start { workflow, status, resume_if_exists, continue_session }) => {
             info!("Starting timer with workflow: {:?}, status: {:?}", workflow, status);
             
+            // Idempotent "make sure a pomodoro is running", unlike a toggle it never pauses
+            if resume_if_exists {
+                let timer_lock = timer.lock().await;
+                match timer_lock.get_info().state {
+                    TimerState::Running => {
+                        info!("Timer is already running");
+                        return Ok(());
+                    }
+                    TimerState::Paused | TimerState::WaitingForStart => {
+                        timer_lock.send_command(TimerCommand::Resume).await?;
+                        update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+                        info!("Timer resumed");
+                        return Ok(());
+                    }
+                    TimerState::Idle | TimerState::Completed => {}
+                }
+            }
+            
+            // A plain `start` while waiting begins the next phase instead of restarting
+            if workflow.is_none() && status.is_none() {
+                let timer_lock = timer.lock().await;
+                if timer_lock.get_info().state == TimerState::WaitingForStart {
+                    timer_lock.send_command(TimerCommand::Resume).await?;
+                    update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+                    info!("Next phase started");
+                    return Ok(());
+                }
+            }
+            
             let workflow_obj = if let Some(workflow_name) = workflow {
                 workflow_manager.get_workflow(&workflow_name).ok_or_else(|| {
                     error!("Workflow '{}' not found", workflow_name);
@@ -159,30 +582,128 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 })?
             };
             
+            let timer_lock = timer.lock().await;
+            
+            if continue_session {
+                let stopped = timer_lock.get_info().last_stopped.filter(|s| s.workflow == workflow_obj.name);
+                match stopped {
+                    Some(stopped) => info!("Continuing '{}' with {} already done",
+                        stopped.phase, waybar::format_time_remaining(stopped.elapsed)),
+                    None => info!("No stopped session of '{}' to continue, starting from its first phase",
+                        workflow_obj.name),
+                }
+            }
+            
+            timer_lock.send_command(TimerCommand::Start {
+                workflow: Some(workflow_obj.clone()),
+                status: Some(status_obj.clone()),
+                continue_session,
+            }).await?;
+            
+            // Update waybar
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+            
+            info!("Timer started with workflow '{}' and status '{}'", 
+                  workflow_obj.name, status_obj);
+        }
+        Some(Commands::Run { workflow, status }) => {
+            let workflow_name = workflow.unwrap_or_else(|| config::get().default_workflow);
+            let workflow_obj = workflow_manager.get_workflow(&workflow_name).ok_or_else(|| {
+                error!("Workflow '{}' not found", workflow_name);
+                "Workflow not found"
+            })?;
+            
+            let status_name = status.unwrap_or_else(|| config::get().default_status);
+            let status_obj = status_manager.get_status(&status_name).ok_or_else(|| {
+                error!("Status '{}' not found", status_name);
+                "Status not found"
+            })?;
+            
+            let timer_lock = timer.lock().await;
+            let mut events = timer_lock.subscribe();
+            timer_lock.send_command(TimerCommand::Start {
+                workflow: Some(workflow_obj.clone()),
+                status: Some(status_obj),
+                continue_session: false,
+            }).await?;
+            
+            // `send_command` refuses workflows without phases
+            let Some(mut current) = workflow_obj.phases.first().cloned() else {
+                return Ok(());
+            };
+            println!("▶ {}: starting {} ({})", workflow_obj.name, current.name, phase_length(&current));
+            
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(TimerEvent::PhaseChanged { phase, .. }) => {
+                            println!("✓ {} complete ({}) — starting {} ({})",
+                                current.name, phase_length(&current), phase.name, phase_length(&phase));
+                            current = phase;
+                        }
+                        Ok(TimerEvent::Completed) => {
+                            println!("✓ {} complete ({}) — {} finished",
+                                current.name, phase_length(&current), workflow_obj.name);
+                            break;
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = ctrl_c() => {
+                        // The timer lives in this process, so don't leave it looking like it's running
+                        timer_lock.send_command(TimerCommand::Stop { save_session: false }).await?;
+                        println!("Stopped");
+                        break;
+                    }
+                }
+            }
+            
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+        }
+        Some(Commands::Go { alias }) => {
+            let alias_config = config::get().aliases.get(&alias).cloned().ok_or_else(|| {
+                error!("Alias '{}' not found", alias);
+                "Alias not found"
+            })?;
+            
+            // Check both names up front so a typo in the config doesn't half-start anything
+            let workflow_obj = workflow_manager.get_workflow(&alias_config.workflow);
+            let status_obj = status_manager.get_status(&alias_config.status);
+            if workflow_obj.is_none() {
+                error!("Alias '{}' uses unknown workflow '{}'", alias, alias_config.workflow);
+            }
+            if status_obj.is_none() {
+                error!("Alias '{}' uses unknown status '{}'", alias, alias_config.status);
+            }
+            let (Some(workflow_obj), Some(status_obj)) = (workflow_obj, status_obj) else {
+                return Err("Alias refers to a missing workflow or status".into());
+            };
+            
             let timer_lock = timer.lock().await;
             timer_lock.send_command(TimerCommand::Start {
                 workflow: Some(workflow_obj.clone()),
                 status: Some(status_obj.clone()),
+                continue_session: false,
             }).await?;
             
             // Update waybar
-            update_waybar_output(&timer_lock.get_info())?;
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
             
             info!("Timer started with workflow '{}' and status '{}'", 
-                  workflow_obj.name, status_obj.name);
+                  workflow_obj.name, status_obj);
         }
-        Some(Commands::Stop) => {
+        Some(Commands::Stop { save_session }) => {
             info!("Stopping timer");
             
             let timer_lock = timer.lock().await;
-            timer_lock.send_command(TimerCommand::Stop).await?;
+            timer_lock.send_command(TimerCommand::Stop { save_session }).await?;
             
             // Update waybar
-            update_waybar_output(&timer_lock.get_info())?;
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
             
             info!("Timer stopped");
         }
-        Some(Commands::Pause) => {
+        Some(Commands::Pause { .. }) => {
             info!("Pausing timer");
             
             let timer_lock = timer.lock().await;
@@ -199,115 +720,477 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             
             // Get updated info and update waybar
             let updated_info = timer_lock.get_info();
-            update_waybar_output(&updated_info)?;
+            update_waybar_output(&updated_info, &status_manager)?;
             
             info!("Timer paused");
         }
-        Some(Commands::Resume) => {
+        Some(Commands::Resume { .. }) => {
             info!("Resuming timer");
             
             let timer_lock = timer.lock().await;
             timer_lock.send_command(TimerCommand::Resume).await?;
             
             // Update waybar
-            update_waybar_output(&timer_lock.get_info())?;
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
             
             info!("Timer resumed");
         }
-        Some(Commands::Skip) => {
+        Some(Commands::Skip { silent }) => {
             info!("Skipping current phase");
             
             let timer_lock = timer.lock().await;
-            timer_lock.send_command(TimerCommand::Skip).await?;
+            
+            // Mandatory breaks have to run their course
+            if let Some(phase) = timer_lock.get_info().current_phase.filter(|p| !p.skippable) {
+                error!("Phase '{}' can't be skipped", phase.name);
+                return Err("Phase can't be skipped".into());
+            }
+            
+            timer_lock.send_command(TimerCommand::Skip { silent }).await?;
             
             // Update waybar
-            update_waybar_output(&timer_lock.get_info())?;
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
             
             info!("Phase skipped");
         }
-        Some(Commands::Status { name }) => {
+        Some(Commands::Rep) => {
+            let timer_lock = timer.lock().await;
+            
+            if timer_lock.get_info().reps_remaining.is_none() {
+                error!("The current phase isn't rep-based");
+                return Err("No rep-based phase is running".into());
+            }
+            
+            timer_lock.send_command(TimerCommand::Rep).await?;
+            
+            // Update waybar
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+            
+            info!("Rep counted");
+        }
+        Some(Commands::Previous { keep_progress, silent }) => {
+            info!("Going back to the previous phase");
+            
+            let timer_lock = timer.lock().await;
+            
+            if let Some(phase) = timer_lock.get_info().current_phase.filter(|p| !p.skippable) {
+                error!("Phase '{}' can't be skipped", phase.name);
+                return Err("Phase can't be skipped".into());
+            }
+            
+            timer_lock.send_command(TimerCommand::Previous { keep_progress, silent }).await?;
+            
+            // Update waybar
+            update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+            
+            info!("Back to the previous phase");
+        }
+        Some(Commands::Status { name: Some(name), restart, .. }) => {
             info!("Setting status to: {}", name);
             
             // Get the status from the manager
             if let Some(status) = status_manager.get_status(&name) {
-                // Start the timer with current workflow but new status
                 let timer_lock = timer.lock().await;
                 let info = timer_lock.get_info();
+                let active = matches!(
+                    info.state,
+                    TimerState::Running | TimerState::Paused | TimerState::WaitingForStart
+                );
                 
-                timer_lock.send_command(TimerCommand::Start {
-                    workflow: info.current_workflow,
-                    status: Some(status.clone()),
-                }).await?;
+                if active && !restart {
+                    // Relabel the session in place, keeping the phase and remaining time
+                    timer_lock.send_command(TimerCommand::SetStatus {
+                        status: status.clone(),
+                    }).await?;
+                } else {
+                    // Start the timer with current workflow but new status
+                    timer_lock.send_command(TimerCommand::Start {
+                        workflow: info.current_workflow.as_deref().cloned(),
+                        status: Some(status.clone()),
+                        continue_session: false,
+                    }).await?;
+                }
                 
                 // Update waybar
-                update_waybar_output(&timer_lock.get_info())?;
+                update_waybar_output(&timer_lock.get_info(), &status_manager)?;
                 
-                info!("Status changed to '{}'", name);
+                info!("Status changed to '{}'", status);
             } else {
                 error!("Status '{}' not found", name);
                 return Err("Status not found".into());
             }
         }
+        Some(Commands::CycleStatus { reverse }) => {
+            let config = config::get();
+            let timer_lock = timer.lock().await;
+            let info = timer_lock.get_info();
+            let active = matches!(
+                info.state,
+                TimerState::Running | TimerState::Paused | TimerState::WaitingForStart
+            );
+            
+            // Cycle the running session's status, or the default one while idle
+            let current = match &info.current_status {
+                Some(status) if active => status.name.clone(),
+                _ => config.default_status.clone(),
+            };
+            
+            let Some(status) = status_manager.next_status(Some(&current), &config.status_order, reverse) else {
+                error!("No statuses to cycle through");
+                return Err("No statuses to cycle through".into());
+            };
+            
+            if active {
+                timer_lock.send_command(TimerCommand::SetStatus {
+                    status: status.clone(),
+                }).await?;
+                
+                // Update waybar
+                update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+                
+                info!("Status changed to '{}'", status);
+            } else {
+                let mut new_config = config;
+                new_config.default_status = status.name.clone();
+                
+                match config::update(new_config) {
+                    Ok(_) => info!("Default status set to '{}'", status.name),
+                    Err(e) => {
+                        error!("Failed to save configuration: {}", e);
+                        return Err(e.into());
+                    }
+                }
+                
+                update_waybar_output(&timer_lock.get_info(), &status_manager)?;
+            }
+        }
+        Some(Commands::Status { name: None, action, .. }) => match action {
+            Some(StatusCommands::List { names_only }) => {
+                info!("Listing statuses");
+                
+                let mut statuses = status_manager.list_statuses();
+                statuses.sort_by(|a, b| a.name.cmp(&b.name));
+                
+                if names_only {
+                    for status in statuses {
+                        println!("{}", status.name);
+                    }
+                    return Ok(());
+                }
+                
+                println!("Available statuses:");
+                for status in statuses {
+                    println!("- {} ({})",
+                        status,
+                        status.description.clone().unwrap_or_else(|| "No description".to_string()));
+                }
+            }
+            Some(StatusCommands::Show { name }) => {
+                let Some(status) = status_manager.get_status(&name) else {
+                    error!("Status '{}' not found", name);
+                    return Err("Status not found".into());
+                };
+                
+                let or_none = |value: Option<String>| value.unwrap_or_else(|| "None".to_string());
+                println!("Name: {}", status.name);
+                println!("Icon: {}", or_none(status.icon));
+                println!("Color: {}", or_none(status.color));
+                println!("Description: {}", or_none(status.description));
+                println!("Default: {}", if config::get().default_status == status.name { "yes" } else { "no" });
+            }
+            Some(StatusCommands::SetDefault { name }) => {
+                info!("Setting default status to: {}", name);
+                
+                if status_manager.get_status(&name).is_none() {
+                    error!("Status '{}' not found", name);
+                    return Err("Status not found".into());
+                }
+                
+                let mut new_config = config::get();
+                new_config.default_status = name.clone();
+                
+                match config::update(new_config) {
+                    Ok(_) => info!("Default status set to '{}'", name),
+                    Err(e) => {
+                        error!("Failed to save configuration: {}", e);
+                        return Err(e.into());
+                    }
+                }
+            }
+            None => {
+                error!("No status name or action given");
+                return Err("Specify a status name or an action, see --help".into());
+            }
+        },
         Some(Commands::Workflow { action }) => match action {
-            WorkflowCommands::List => {
+            WorkflowCommands::List { names_only } => {
                 info!("Listing workflows");
                 
                 let workflows = workflow_manager.list_workflows();
+                
+                if names_only {
+                    let mut names: Vec<String> = workflows.into_iter().map(|w| w.name).collect();
+                    names.sort();
+                    for name in names {
+                        println!("{}", name);
+                    }
+                    return Ok(());
+                }
+                
                 println!("Available workflows:");
                 
                 for workflow in workflows {
+                    let name = match &workflow.icon {
+                        Some(icon) => format!("{} {}", icon, workflow.name),
+                        None => workflow.name.clone(),
+                    };
                     println!("- {} ({})", 
-                        workflow.name, 
+                        name, 
                         workflow.description.unwrap_or_else(|| "No description".to_string()));
                     
                     println!("  Phases:");
                     for phase in workflow.phases {
-                        println!("  - {} ({} minutes)", phase.name, phase.duration);
+                        println!("  - {}", phase);
                     }
                     println!();
                 }
             }
-            WorkflowCommands::Add { name, phases } => {
-                info!("Adding workflow '{}' with phases: {}", name, phases);
-                
-                // Parse phases
-                match Workflow::parse_phases(&phases) {
-                    Ok(parsed_phases) => {
-                        let workflow = Workflow::new(&name)
-                            .with_phases(parsed_phases)
-                            .with_repeatable(true);
+            WorkflowCommands::Add { name, phases, from_template, phases_file, file, icon } => {
+                let mut workflow = match (phases, from_template, phases_file) {
+                    (Some(phases), _, _) => {
+                        info!("Adding workflow '{}' with phases: {}", name, phases);
                         
-                        match workflow_manager.add_workflow(workflow) {
-                            Ok(_) => info!("Workflow '{}' added successfully", name),
+                        match Workflow::parse_phases(&phases) {
+                            Ok(parsed_phases) => Workflow::new(&name)
+                                .with_phases(parsed_phases)
+                                .with_repeatable(true),
                             Err(e) => {
-                                error!("Failed to add workflow: {}", e);
+                                error!("Failed to parse phases: {}", e);
                                 return Err(e.into());
                             }
                         }
                     }
+                    (None, Some(template_id), _) => {
+                        info!("Adding workflow '{}' from template '{}'", name, template_id);
+                        
+                        let Some(mut template) = workflow::template(&template_id) else {
+                            let ids: Vec<&str> = workflow::templates().into_iter().map(|(id, _)| id).collect();
+                            error!("Unknown template '{}', available: {}", template_id, ids.join(", "));
+                            return Err(format!("Unknown template '{}'", template_id).into());
+                        };
+                        template.name = name.clone();
+                        template
+                    }
+                    (None, None, Some(path)) => {
+                        info!("Adding workflow '{}' with phases from {}", name, path.display());
+                        
+                        let content = std::fs::read_to_string(&path).map_err(|e| {
+                            error!("Failed to read {}: {}", path.display(), e);
+                            format!("Failed to read phases file: {}", e)
+                        })?;
+                        
+                        match Workflow::parse_phases_file(&content) {
+                            Ok(parsed_phases) => Workflow::new(&name)
+                                .with_phases(parsed_phases)
+                                .with_repeatable(true),
+                            Err(e) => {
+                                error!("Failed to parse {}: {}", path.display(), e);
+                                return Err(e.into());
+                            }
+                        }
+                    }
+                    // clap requires one of the three
+                    (None, None, None) => unreachable!(),
+                };
+                if let Some(icon) = &icon {
+                    workflow = workflow.with_icon(icon);
+                }
+                
+                if let Some(file_path) = file {
+                    match WorkflowManager::write_workflow_file(&workflow, &file_path) {
+                        Ok(_) => info!("Workflow '{}' written to {}", name, file_path.display()),
+                        Err(e) => {
+                            error!("Failed to write workflow file: {}", e);
+                            std::process::exit(e.exit_code());
+                        }
+                    }
+                    return Ok(());
+                }
+                
+                match workflow_manager.add_workflow(workflow) {
+                    Ok(_) => info!("Workflow '{}' added successfully", name),
                     Err(e) => {
-                        error!("Failed to parse phases: {}", e);
-                        return Err(e.into());
+                        error!("Failed to add workflow: {}", e);
+                        std::process::exit(e.exit_code());
                     }
                 }
             }
-            WorkflowCommands::Remove { name } => {
+            WorkflowCommands::Templates => {
+                for (id, template) in workflow::templates() {
+                    let phases: Vec<String> = template.phases.iter()
+                        .map(|phase| format!("{} {}m", phase.name, phase.duration))
+                        .collect();
+                    println!("{:<18} {}", id, phases.join(", "));
+                    if let Some(description) = template.description {
+                        println!("{:<18} {}", "", description);
+                    }
+                }
+            }
+            WorkflowCommands::Remove { name, dry_run } => {
                 info!("Removing workflow: {}", name);
                 
+                if dry_run {
+                    if let Err(e) = workflow_manager.check_removable(&name) {
+                        error!("Failed to remove workflow: {}", e);
+                        std::process::exit(e.exit_code());
+                    }
+                    
+                    println!("Would remove workflow '{}' from {}", name,
+                        WorkflowManager::get_workflow_file().display());
+                    if config::get().default_workflow == name {
+                        println!("Note: '{}' is the default workflow, `start` would fail until another is set", name);
+                    }
+                    return Ok(());
+                }
+                
                 match workflow_manager.remove_workflow(&name) {
                     Ok(_) => info!("Workflow '{}' removed successfully", name),
                     Err(e) => {
                         error!("Failed to remove workflow: {}", e);
+                        std::process::exit(e.exit_code());
+                    }
+                }
+            }
+            WorkflowCommands::Validate { name } => {
+                let workflow = workflow_manager.get_workflow(&name).ok_or_else(|| {
+                    error!("Workflow '{}' not found", name);
+                    "Workflow not found"
+                })?;
+                
+                let warnings = workflow.validation_warnings();
+                if warnings.is_empty() {
+                    println!("Workflow '{}' looks good", name);
+                } else {
+                    println!("Workflow '{}' has {} problem(s):", name, warnings.len());
+                    for warning in &warnings {
+                        println!("- {}", warning);
+                    }
+                    return Err("Workflow validation failed".into());
+                }
+            }
+            WorkflowCommands::Stats { name } => {
+                let names: Vec<String> = workflow_manager.list_workflows().into_iter().map(|w| w.name).collect();
+                let usage = history::workflow_usage(&history::load(), &names);
+                let last_used = |u: &history::WorkflowUsage| {
+                    u.last_used.map_or_else(|| "never".to_string(), |time| time.format("%Y-%m-%d").to_string())
+                };
+                
+                let Some(name) = name else {
+                    println!("{:<24}  {:>8}  {:>8}  Last used", "Workflow", "Sessions", "Minutes");
+                    for u in &usage {
+                        println!("{:<24}  {:>8}  {:>8}  {}", u.name, u.sessions, u.seconds / 60, last_used(u));
+                    }
+                    return Ok(());
+                };
+                
+                let Some(u) = usage.iter().find(|u| u.name == name) else {
+                    error!("Workflow '{}' not found", name);
+                    return Err("Workflow not found".into());
+                };
+                
+                println!("Workflow: {}", u.name);
+                println!("Sessions: {}", u.sessions);
+                println!("Minutes: {}", u.seconds / 60);
+                println!("Last used: {}", last_used(u));
+            }
+            WorkflowCommands::Import { dir } => {
+                info!("Importing workflows from: {}", dir.display());
+                
+                let report = match workflow_manager.import_dir(&dir) {
+                    Ok(report) => report,
+                    Err(e) => {
+                        error!("Failed to import workflows: {}", e);
+                        std::process::exit(e.exit_code());
+                    }
+                };
+                
+                let skipped: Vec<String> = report.skipped
+                    .iter()
+                    .map(|(source, reason)| format!("{} — {}", source, reason))
+                    .collect();
+                
+                if skipped.is_empty() {
+                    println!("imported {}", report.imported.len());
+                } else {
+                    println!("imported {}, skipped {}: {}",
+                        report.imported.len(), skipped.len(), skipped.join("; "));
+                }
+            }
+            WorkflowCommands::SetDefault { name } => {
+                info!("Setting default workflow to: {}", name);
+                
+                if workflow_manager.get_workflow(&name).is_none() {
+                    error!("Workflow '{}' not found", name);
+                    return Err("Workflow not found".into());
+                }
+                
+                let mut new_config = config::get();
+                new_config.default_workflow = name.clone();
+                
+                match config::update(new_config) {
+                    Ok(_) => info!("Default workflow set to '{}'", name),
+                    Err(e) => {
+                        error!("Failed to save configuration: {}", e);
                         return Err(e.into());
                     }
                 }
             }
         },
-        Some(Commands::Info) => {
+        Some(Commands::Info { field, porcelain }) => {
             let timer_lock = timer.lock().await;
             let info = timer_lock.get_info();
             
-            println!("Timer State: {:?}", info.state);
+            // One bare value for prompts; missing values print nothing rather than failing
+            if let Some(field) = field {
+                let active = info.current_phase.is_some();
+                let value = match field {
+                    InfoField::Remaining => info.time_remaining.map(waybar::format_time_remaining),
+                    InfoField::Phase => info.current_phase.as_ref().map(|p| p.name.clone()),
+                    InfoField::Status => info.current_status.as_ref().map(|s| s.name.clone()),
+                    InfoField::State => Some(info.state.porcelain_name().to_string()),
+                    InfoField::Percentage => info.current_phase.as_ref()
+                        .and_then(|p| waybar::current_phase_percentage(&info, p))
+                        .map(|percent| percent.to_string()),
+                    InfoField::Elapsed => active.then(|| waybar::format_time_remaining(info.elapsed_time)),
+                };
+                print!("{}", value.unwrap_or_default());
+                return Ok(());
+            }
+            
+            // These keys are a stable contract: add new ones, never rename or drop them
+            if porcelain {
+                let name_or_empty = |name: Option<&String>| name.cloned().unwrap_or_default();
+                let percentage = info.current_phase.as_ref()
+                    .and_then(|p| waybar::current_phase_percentage(&info, p));
+                
+                println!("state={}", info.state.porcelain_name());
+                println!("workflow={}", name_or_empty(info.current_workflow.as_ref().map(|w| &w.name)));
+                println!("status={}", name_or_empty(info.current_status.as_ref().map(|s| &s.name)));
+                println!("phase={}", name_or_empty(info.current_phase.as_ref().map(|p| &p.name)));
+                println!("remaining_seconds={}", info.time_remaining.map(|r| r.num_seconds().max(0).to_string()).unwrap_or_default());
+                println!("elapsed_seconds={}", info.elapsed_time.num_seconds());
+                println!("session_seconds={}", info.session_elapsed.num_seconds());
+                println!("percentage={}", percentage.map(|p| p.to_string()).unwrap_or_default());
+                println!("cycle={}", info.cycle_count);
+                println!("started_at={}", info.start_time.map(|t| t.to_rfc3339()).unwrap_or_default());
+                println!("paused_at={}", info.pause_time.map(|t| t.to_rfc3339()).unwrap_or_default());
+                println!("reps_remaining={}", info.reps_remaining.map(|r| r.to_string()).unwrap_or_default());
+                println!("paused_seconds={}", info.phase_paused().num_seconds());
+                println!("wall_seconds={}", info.start_time.map(|t| (clock::now() - t).num_seconds().to_string()).unwrap_or_default());
+                return Ok(());
+            }
+            
+            println!("Timer State: {}", info.state);
             
             if let Some(workflow) = &info.current_workflow {
                 println!("Current Workflow: {}", workflow.name);
@@ -316,47 +1199,228 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
             
             if let Some(status) = &info.current_status {
-                println!("Current Status: {}", status.name);
+                println!("Current Status: {}", status);
             } else {
                 println!("Current Status: None");
             }
             
             if let Some(phase) = &info.current_phase {
-                println!("Current Phase: {} ({} minutes)", phase.name, phase.duration);
+                println!("Current Phase: {}", phase);
             } else {
                 println!("Current Phase: None");
             }
             
-            if let Some(remaining) = &info.time_remaining {
-                let total_seconds = remaining.num_seconds();
-                let minutes = total_seconds / 60;
-                let seconds = total_seconds % 60;
-                println!("Time Remaining: {:02}:{:02}", minutes, seconds);
+            if let Some(reps) = info.reps_remaining {
+                println!("Reps Left: {}", reps);
+            } else if let Some(remaining) = info.time_remaining {
+                println!("Time Remaining: {}", waybar::format_time_remaining(remaining));
             } else {
                 println!("Time Remaining: None");
             }
             
-            let elapsed_seconds = info.elapsed_time.num_seconds();
-            let elapsed_minutes = elapsed_seconds / 60;
-            let elapsed_secs = elapsed_seconds % 60;
-            println!("Elapsed Time: {:02}:{:02}", elapsed_minutes, elapsed_secs);
+            // Elapsed and session time are active time; pauses are counted separately
+            println!("Elapsed Time: {}", waybar::format_time_remaining(info.elapsed_time));
+            println!("Paused Time: {}", waybar::format_time_remaining(info.phase_paused()));
+            println!("Session Time: {}", waybar::format_time_remaining(info.session_elapsed));
+            if let Some(start_time) = info.start_time {
+                println!("Since Start: {}", waybar::format_time_remaining(clock::now() - start_time));
+            }
+            
+            if let Some(start_time) = &info.start_time {
+                println!("Started at: {}", start_time.format("%H:%M:%S"));
+            }
+            
+            if info.state == TimerState::Paused {
+                if let Some(pause_time) = &info.pause_time {
+                    println!("Paused at: {}", pause_time.format("%H:%M:%S"));
+                }
+            }
+        }
+        Some(Commands::NextBreak) => {
+            let timer_lock = timer.lock().await;
+            let info = timer_lock.get_info();
+            
+            let (Some(workflow), Some(phase)) = (&info.current_workflow, &info.current_phase) else {
+                println!("Timer is not running");
+                return Ok(());
+            };
+            
+            let next_break = workflow.phases.iter()
+                .position(|p| p.name == phase.name)
+                .and_then(|index| workflow.minutes_until_next_break(index));
+            
+            match next_break {
+                Some(minutes) => {
+                    let remaining = info.time_remaining
+                        .unwrap_or_else(|| chrono::Duration::minutes(phase.duration as i64));
+                    let until_break = remaining + chrono::Duration::minutes(minutes as i64);
+                    println!("Next break in {}", waybar::format_time_remaining(until_break));
+                }
+                None => println!("No break scheduled"),
+            }
+        }
+        Some(Commands::SchedulePause { at, cancel }) => {
+            let Some(at) = at.filter(|_| !cancel) else {
+                if let Err(e) = schedule::clear_pause_at() {
+                    error!("{}", e);
+                    return Err(e.into());
+                }
+                info!("Scheduled pause cleared");
+                return Ok(());
+            };
+            
+            let pause_at = match schedule::next_occurrence(at) {
+                Ok(pause_at) => pause_at,
+                Err(e) => {
+                    error!("{}", e);
+                    return Err(e.into());
+                }
+            };
+            if let Err(e) = schedule::set_pause_at(pause_at) {
+                error!("{}", e);
+                return Err(e.into());
+            }
+            
+            // Only the daemon watches the clock, so without one nothing will pause
+            if !daemon::is_running().unwrap_or(true) {
+                warn!("No daemon is running; the pause only happens while one is");
+            }
+            println!("Will pause at {}", pause_at.format("%H:%M"));
         }
-        Some(Commands::Daemon) => {
+        Some(Commands::SetFormat { format, save, clear }) => {
+            let override_path = waybar::get_format_override_path();
+            
+            let Some(format) = format.filter(|_| !clear) else {
+                if override_path.exists() {
+                    if let Err(e) = std::fs::remove_file(&override_path) {
+                        error!("Failed to clear the format override: {}", e);
+                        return Err(e.into());
+                    }
+                }
+                info!("Using the configured format again");
+                update_waybar_output(&timer.lock().await.get_info(), &status_manager)?;
+                return Ok(());
+            };
+            
+            if let Err(e) = waybar::validate_format(&format) {
+                error!("Invalid format: {}", e);
+                return Err(e.into());
+            }
+            
+            if save {
+                let mut new_config = config::get();
+                new_config.waybar_integration.format = format.clone();
+                if let Err(e) = config::update(new_config) {
+                    error!("Failed to save configuration: {}", e);
+                    return Err(e.into());
+                }
+                
+                // The saved format takes over, so the override is no longer needed
+                if override_path.exists() {
+                    std::fs::remove_file(&override_path)?;
+                }
+                info!("Format saved to the config");
+            } else {
+                if let Some(parent) = override_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                if let Err(e) = std::fs::write(&override_path, &format) {
+                    error!("Failed to write the format override: {}", e);
+                    return Err(e.into());
+                }
+                info!("Format set until `set-format --clear`");
+            }
+            
+            update_waybar_output(&timer.lock().await.get_info(), &status_manager)?;
+        }
+        Some(Commands::Sessions { limit }) => {
+            let entries = history::load_recent(limit);
+            
+            if entries.is_empty() {
+                println!("No sessions recorded yet");
+                return Ok(());
+            }
+            
+            let round_to_minutes = config::get().history_round_to_minutes;
+            println!("{:<16}  {:<12}  {:<16}  {:>8}  Interrupted", "Time", "Status", "Phase", "Duration");
+            for entry in entries {
+                println!("{:<16}  {:<12}  {:<16}  {:>8}  {}",
+                    entry.started_at.format("%Y-%m-%d %H:%M"),
+                    entry.status.as_deref().unwrap_or("-"),
+                    entry.phase,
+                    waybar::format_time_remaining(chrono::Duration::seconds(entry.display_seconds(round_to_minutes) as i64)),
+                    if entry.interrupted { "yes" } else { "" });
+            }
+        }
+        Some(Commands::Stats) => {
+            let entries = history::load();
+            let today = clock::now().date_naive();
+            let week_start = history::week_start(today, config::get().weekly_report_day);
+            let (today_phases, today_minutes) = history::work_summary(&entries, today);
+            let (week_phases, week_minutes) = history::work_summary_between(&entries, week_start, today);
+            let streak = history::streak_from(&entries, today);
+            
+            println!("Today: {} work phases, {} minutes", today_phases, today_minutes);
+            println!("This week: {} work phases, {}h {:02}m", week_phases, week_minutes / 60, week_minutes % 60);
+            println!("Streak: {} day{}", streak, if streak == 1 { "" } else { "s" });
+        }
+        // Handled before the timer is created
+        #[cfg(feature = "bench")]
+        Some(Commands::Bench) => unreachable!(),
+        Some(Commands::EnsureDaemon) => match daemon::ensure_running(cli.config.as_deref()) {
+            Ok(Some(pid)) => info!("Daemon started (pid {})", pid),
+            Ok(None) => info!("Daemon is already running"),
+            Err(e) => {
+                error!("{}", e);
+                return Err(e.into());
+            }
+        },
+        Some(Commands::Daemon {
+            interval,
+            #[cfg(feature = "http")]
+            http,
+            record_events,
+        }) => {
             info!("Starting in daemon mode");
             
+            let update_interval_ms = match interval {
+                Some(interval_ms) => interval_ms,
+                None => {
+                    let configured = config::get().waybar_integration.update_interval_ms;
+                    if configured < MIN_UPDATE_INTERVAL_MS {
+                        warn!("update_interval_ms {} is below the minimum, using {}ms",
+                              configured, MIN_UPDATE_INTERVAL_MS);
+                    }
+                    configured.max(MIN_UPDATE_INTERVAL_MS)
+                }
+            };
+            info!("Updating Waybar every {}ms", update_interval_ms);
+            
+            #[cfg(not(unix))]
+            if config::get().waybar_integration.socket_path.is_some() {
+                warn!(
+                    "socket_path needs Unix sockets, which this platform doesn't have; Waybar output only goes to {}",
+                    waybar::get_waybar_output_path().display()
+                );
+            }
+            
             // Create a timer to update waybar periodically
             let timer_clone = Arc::clone(&timer);
+            let status_manager_clone = status_manager.clone();
             
             // Create a task to handle signals for clean shutdown
             tokio::spawn(async move {
-                match ctrl_c().await {
-                    Ok(()) => {
-                        info!("Received shutdown signal, saving state and exiting");
+                match shutdown_signal().await {
+                    Ok(signal_name) => {
+                        info!("Received {}, saving state and exiting", signal_name);
                         
-                        // Last state update before shutdown
+                        // Persist the latest progress, the periodic save may be up to 10s behind
                         let timer_lock = timer_clone.lock().await;
+                        timer_lock.save_state();
+                        
+                        // Last state update before shutdown
                         let info = timer_lock.get_info();
-                        update_waybar_output(&info).unwrap_or_else(|e| {
+                        update_waybar_output(&info, &status_manager_clone).unwrap_or_else(|e| {
                             error!("Failed to update waybar output: {}", e);
                         });
                         
@@ -366,24 +1430,162 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             });
             
+            if let Some(path) = record_events {
+                match recorder::open(&path) {
+                    Ok(file) => {
+                        info!("Recording timer events to {}", path.display());
+                        tokio::spawn(recorder::record(timer.lock().await.subscribe(), file));
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            
             // Set up timer state socket listener for IPC
             // TODO: Implement IPC socket if needed
             
+            #[cfg(feature = "http")]
+            if let Some(addr) = http {
+                let timer_clone = Arc::clone(&timer);
+                tokio::spawn(async move {
+                    if let Err(e) = http::serve(addr, timer_clone).await {
+                        error!("HTTP server stopped: {}", e);
+                    }
+                });
+            }
+            
+            let config = config::get();
+            if let Some(command) = config.idle_detect_command.filter(|c| !c.trim().is_empty()) {
+                info!("Pausing the timer while idle, checking every {}s", config.idle_poll_seconds);
+                tokio::spawn(idle::watch(Arc::clone(&timer), command, config.idle_poll_seconds));
+            }
+            tokio::spawn(schedule::watch(Arc::clone(&timer)));
+            
             // Start the main daemon loop
             let timer_clone = Arc::clone(&timer);
+            let mut waybar_writes = WaybarWrites::default();
             loop {
                 // Get timer info and update waybar
                 let timer_lock = timer_clone.lock().await;
                 let info = timer_lock.get_info();
-                update_waybar_output(&info)?;
+                drop(timer_lock); // Release the lock before writing and sleeping
                 
-                // Sleep for a short duration
-                drop(timer_lock); // Release the lock before sleeping
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                let delay_ms = waybar_writes.update(&info, &status_manager, update_interval_ms);
+                tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+            }
+        }
+        Some(Commands::Config { action: ConfigCommands::Path }) => {
+            println!("Config file:     {}", config::get_loaded_config_path().display());
+            println!("State file:      {}", persistence::get_state_file_path().display());
+            println!("Workflows file:  {}", WorkflowManager::get_workflow_file().display());
+            println!("Workflows dir:   {}", WorkflowManager::get_workflow_dir().display());
+            println!("History file:    {}", history::get_history_file_path().display());
+            println!("Waybar output:   {}", waybar::get_waybar_output_path().display());
+            if let Some(mini_path) = waybar::get_mini_output_path() {
+                println!("Mini output:     {}", mini_path.display());
+            }
+        }
+        Some(Commands::Init) => {
+            if let Err(e) = setup::run_wizard(&workflow_manager, &status_manager) {
+                error!("Setup failed: {}", e);
+                return Err(e.into());
+            }
+        }
+        Some(Commands::ResetAll { keep_config, yes, dry_run }) => {
+            let mut files = vec![
+                persistence::get_state_file_path(),
+                WorkflowManager::get_workflow_file(),
+            ];
+            if !keep_config {
+                files.push(config::get_config_file_path(cli.config.clone()));
+            }
+            let files: Vec<PathBuf> = files.into_iter().filter(|path| path.exists()).collect();
+            
+            if files.is_empty() {
+                println!("Nothing to reset, everything is already at its defaults");
+                return Ok(());
+            }
+            
+            if dry_run {
+                println!("Would move these files aside as .bak and restore defaults:");
+                for path in &files {
+                    println!("  {}", path.display());
+                }
+                return Ok(());
+            }
+            
+            if !yes {
+                if !std::io::stdin().is_terminal() {
+                    error!("Refusing to reset without confirmation, pass --yes");
+                    return Err("Confirmation required".into());
+                }
+                
+                println!("This resets the following files (old versions are kept as .bak):");
+                for path in &files {
+                    println!("  {}", path.display());
+                }
+                
+                let confirmed = dialoguer::Confirm::new()
+                    .with_prompt("Reset to defaults?")
+                    .default(false)
+                    .interact()
+                    .map_err(|e| format!("Failed to read confirmation: {}", e))?;
+                if !confirmed {
+                    println!("Nothing was changed");
+                    return Ok(());
+                }
+            }
+            
+            // Moving the files aside is enough, defaults are regenerated when they're missing
+            for path in &files {
+                let mut backup = path.clone().into_os_string();
+                backup.push(".bak");
+                
+                if let Err(e) = std::fs::rename(path, &backup) {
+                    error!("Failed to back up {}: {}", path.display(), e);
+                    return Err(e.into());
+                }
+                println!("Moved {} to {}", path.display(), PathBuf::from(backup).display());
+            }
+            
+            // Leave a fresh idle state behind rather than the old timer
+            persistence::save_state(&persistence::PersistentState::default())?;
+            update_waybar_output(&timer::TimerInfo::default(), &status_manager)?;
+            
+            println!("Reset complete");
+        }
+        Some(Commands::Version { verbose }) => {
+            println!("tomato-clock {}", env!("CARGO_PKG_VERSION"));
+            
+            if verbose {
+                // Set by build.rs; vergen emits a placeholder when built outside a git checkout
+                let commit = option_env!("VERGEN_GIT_SHA")
+                    .filter(|sha| *sha != "VERGEN_IDEMPOTENT_OUTPUT")
+                    .unwrap_or("unknown");
+                let dirty = option_env!("VERGEN_GIT_DIRTY") == Some("true");
+                println!("commit: {}{}", commit, if dirty { " (dirty)" } else { "" });
+                println!("built: {}", option_env!("VERGEN_BUILD_TIMESTAMP").unwrap_or("unknown"));
+                println!(
+                    "rustc: {} ({})",
+                    option_env!("VERGEN_RUSTC_SEMVER").unwrap_or("unknown"),
+                    option_env!("VERGEN_RUSTC_HOST_TRIPLE").unwrap_or("unknown")
+                );
             }
         }
         None => {
             info!("No command specified, starting in interactive mode");
+            
+            // Offer the setup wizard on first run when someone is at the keyboard
+            if first_run && std::io::stdin().is_terminal() {
+                if let Err(e) = setup::run_wizard(&workflow_manager, &status_manager) {
+                    error!("Setup failed: {}", e);
+                    return Err(e.into());
+                }
+                return Ok(());
+            }
+            
             // Try to connect to running daemon or start a simple CLI
             println!("No command specified. Use --help to see available commands.");
         }
@@ -398,6 +1600,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 async fn execute_command_with_lock(
     timer: &Arc<AsyncMutex<Timer>>,
     command_lock: &Arc<AsyncMutex<()>>,
+    status_manager: &StatusManager,
     operation: impl FnOnce(&Timer) -> Result<(), Box<dyn std::error::Error>> + Send,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Acquire command lock to prevent concurrent commands
@@ -410,10 +1613,155 @@ async fn execute_command_with_lock(
     operation(&timer_lock)?;
     
     // Update waybar
-    update_waybar_output(&timer_lock.get_info())?;
+    update_waybar_output(&timer_lock.get_info(), status_manager)?;
     
     // Add a small delay to ensure persistence has time to complete
     tokio::time::sleep(StdDuration::from_millis(100)).await;
     
     Ok(())
 }
+
+// Wait for Ctrl+C or, on Unix, SIGTERM (what systemd sends on stop)
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = ctrl_c() => result.map(|_| "Ctrl+C"),
+            _ = sigterm.recv() => Ok("SIGTERM"),
+        }
+    }
+    
+    #[cfg(not(unix))]
+    {
+        ctrl_c().await.map(|_| "Ctrl+C")
+    }
+}
+
+// "25m" for timed phases, "10 reps" for rep-based ones
+fn phase_length(phase: &Phase) -> String {
+    match phase.reps {
+        Some(reps) => format!("{} reps", reps),
+        None => format!("{}m", phase.duration),
+    }
+}
+
+// The daemon's Waybar writes, from one pass of its loop to the next
+#[derive(Default)]
+struct WaybarWrites {
+    consecutive_failures: u32,
+    // What the last successful write put on disk, so a paused pass can tell if anything changed
+    last_written: Option<(WaybarOutput, WaybarOutput)>,
+}
+
+impl WaybarWrites {
+    // Write `info` to the Waybar output and return how long to wait before the next pass.
+    // A failed write shouldn't take the daemon down; back off and try again
+    fn update(&mut self, info: &TimerInfo, status_manager: &StatusManager, update_interval_ms: u64) -> u64 {
+        let Some(rendered) = waybar::render_waybar_output(info, status_manager) else {
+            return update_interval_ms;
+        };
+        
+        // The time left doesn't move while paused, so only a pass that renders something
+        // new (the pause itself, a status, format or config change) is written
+        if info.state == TimerState::Paused && self.last_written.as_ref() == Some(&rendered) {
+            return update_interval_ms;
+        }
+        
+        match waybar::write_rendered_output(rendered.clone()) {
+            Ok(_) => {
+                if self.consecutive_failures > 0 {
+                    info!("Waybar output recovered after {} failed attempts", self.consecutive_failures);
+                }
+                self.consecutive_failures = 0;
+                self.last_written = Some(rendered);
+                update_interval_ms
+            }
+            Err(e) => {
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                error!("Failed to update waybar output (attempt {}): {}", self.consecutive_failures, e);
+                waybar_retry_delay_ms(update_interval_ms, self.consecutive_failures)
+            }
+        }
+    }
+}
+
+// Exponential backoff for retrying a failed Waybar write: 2x, 4x, 8x the interval, capped
+fn waybar_retry_delay_ms(update_interval_ms: u64, consecutive_failures: u32) -> u64 {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    update_interval_ms
+        .saturating_mul(2u64.saturating_pow(exponent + 1))
+        .min(WAYBAR_RETRY_MAX_DELAY_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    
+    use crate::status::Status;
+    use crate::test_support;
+    
+    #[tokio::test]
+    async fn waybar_write_errors_back_off_and_recover() {
+        let _sandbox = test_support::sandbox().await;
+        let info = TimerInfo {
+            state: TimerState::Running,
+            current_phase: Some(Phase::new("Work", 25)),
+            time_remaining: Some(chrono::Duration::minutes(20)),
+            ..Default::default()
+        };
+        
+        // A directory where the output file goes makes every write fail
+        let output = waybar::get_waybar_output_path();
+        fs::create_dir_all(&output).unwrap();
+        let status_manager = StatusManager::new();
+        let mut writes = WaybarWrites::default();
+        assert_eq!(writes.update(&info, &status_manager, 1000), 2000);
+        assert_eq!(writes.update(&info, &status_manager, 1000), 4000);
+        assert_eq!(writes.update(&info, &status_manager, 1000), 8000);
+        assert_eq!(writes.update(&info, &status_manager, 1000), WAYBAR_RETRY_MAX_DELAY_MS);
+        
+        fs::remove_dir(&output).unwrap();
+        assert_eq!(writes.update(&info, &status_manager, 1000), 1000);
+        assert!(output.is_file());
+    }
+    
+    #[tokio::test]
+    async fn paused_output_is_written_again_only_when_it_changes() {
+        let _sandbox = test_support::sandbox().await;
+        let mut info = TimerInfo {
+            state: TimerState::Paused,
+            current_phase: Some(Phase::new("Work", 25)),
+            current_status: Some(Status::new("coding")),
+            time_remaining: Some(chrono::Duration::minutes(20)),
+            ..Default::default()
+        };
+        let output = waybar::get_waybar_output_path();
+        let status_manager = StatusManager::new();
+        let mut writes = WaybarWrites::default();
+        writes.update(&info, &status_manager, 1000);
+        
+        // Removed outputs show which passes wrote one
+        fs::remove_file(&output).unwrap();
+        writes.update(&info, &status_manager, 1000);
+        assert!(!output.exists());
+        
+        // `set-status` while paused changes only the status
+        info.current_status = Some(Status::new("reading"));
+        writes.update(&info, &status_manager, 1000);
+        assert!(fs::read_to_string(&output).unwrap().contains("reading"));
+        
+        // The paused text doesn't use the format, so changing it writes nothing
+        fs::remove_file(&output).unwrap();
+        fs::write(waybar::get_format_override_path(), "{phase} {remaining}").unwrap();
+        writes.update(&info, &status_manager, 1000);
+        assert!(!output.exists());
+        
+        let mut config = config::get();
+        config.waybar_integration.min_width = Some(20);
+        config::set(config);
+        writes.update(&info, &status_manager, 1000);
+        assert!(output.is_file());
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::Duration;
+use log::{error, warn};
+#[cfg(feature = "notifications")]
+use notify_rust::Notification;
+
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::clock;
+use crate::config::{self, NotificationBackend, NotificationConfig};
+use crate::history;
+use crate::status::Status;
+use crate::timer::TimerCommand;
+use crate::workflow::{Phase, Workflow};
+
+/// A way of showing a notification to the user
+pub trait Notifier: Send + Sync {
+    fn notify(&self, summary: &str, body: &str) -> Result<(), String>;
+}
+
+/// Desktop notifications through notify-rust, over D-Bus on Linux
+pub struct DbusNotifier;
+
+impl Notifier for DbusNotifier {
+    #[cfg(feature = "notifications")]
+    fn notify(&self, summary: &str, body: &str) -> Result<(), String> {
+        Notification::new()
+            .appname("Tomato Clock")
+            .summary(summary)
+            .body(body)
+            .show()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to show notification: {}", e))
+    }
+    
+    // Built without the `notifications` feature: nothing to show them with
+    #[cfg(not(feature = "notifications"))]
+    fn notify(&self, _summary: &str, _body: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Runs a shell command for each notification, e.g. `notify-send {summary} {body}`
+/// or a script forwarding them to a phone. `{summary}` and `{body}` are replaced
+/// by the shell-quoted text
+pub struct CommandNotifier {
+    command: String,
+}
+
+impl CommandNotifier {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into() }
+    }
+    
+    // Split on `{summary}` first so a summary containing "{body}" is left alone
+    fn render(&self, summary: &str, body: &str) -> String {
+        self.command
+            .split("{summary}")
+            .map(|part| part.replace("{body}", &shell_quote(body)))
+            .collect::<Vec<_>>()
+            .join(&shell_quote(summary))
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&self, summary: &str, body: &str) -> Result<(), String> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(self.render(summary, body))
+            .spawn()
+            .map_err(|e| format!("Failed to run notification command: {}", e))?;
+        
+        // Like hooks, the command is reaped in the background so it never blocks the timer
+        tokio::spawn(async move {
+            match child.wait().await {
+                Ok(status) if !status.success() => warn!("Notification command exited with {}", status),
+                Err(e) => error!("Failed to wait for notification command: {}", e),
+                _ => {}
+            }
+        });
+        
+        Ok(())
+    }
+}
+
+// Wrap in single quotes, closing and reopening them around any quote in the text
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// The notifier `notification.backend` asks for. The `command` backend needs
+/// `notification.command`, and falls back to desktop notifications without one
+pub fn notifier(config: &NotificationConfig) -> Box<dyn Notifier> {
+    match (config.backend, &config.command) {
+        (NotificationBackend::Dbus, _) => Box::new(DbusNotifier),
+        (NotificationBackend::Command, Some(command)) => Box::new(CommandNotifier::new(command.as_str())),
+        (NotificationBackend::Command, None) => {
+            warn!("notification.backend is \"command\" but notification.command isn't set, using desktop notifications");
+            Box::new(DbusNotifier)
+        }
+    }
+}
+
+pub struct NotificationService {
+    enabled: bool,
+    #[cfg_attr(not(feature = "notifications"), allow(dead_code))]
+    actions: bool,
+    notifier: Box<dyn Notifier>,
+}
+
+impl NotificationService {
+    pub fn new() -> Self {
+        let config = config::get();
+        
+        Self {
+            enabled: config.notification_enabled,
+            // Buttons need notify-rust, so other backends get plain break notifications
+            actions: config.notification_actions && config.notification.backend == NotificationBackend::Dbus,
+            notifier: notifier(&config.notification),
+        }
+    }
+    
+    /// Announce a break that just began, with "Start break" / "Skip break" buttons
+    /// when enabled and supported; otherwise a plain phase change notification
+    pub fn send_break_notification(
+        &self,
+        phase: &Phase,
+        status: &Status,
+        command_tx: mpsc::Sender<TimerCommand>,
+    ) -> Result<(), String> {
+        #[cfg(all(feature = "notifications", unix, not(target_os = "macos")))]
+        if self.enabled && self.actions && supports_actions() {
+            let handle = Notification::new()
+                .appname("Tomato Clock")
+                .summary(&phase.name)
+                .body(&format!("{} ({} min)", status, phase.duration))
+                .action("start", "Start break")
+                .action("skip", "Skip break")
+                .show()
+                .map_err(|e| format!("Failed to show notification: {}", e))?;
+            
+            // Waiting for a click blocks, so keep it off the async workers
+            tokio::task::spawn_blocking(move || {
+                handle.wait_for_action(|action| {
+                    let command = match action {
+                        "start" => TimerCommand::Resume,
+                        "skip" => TimerCommand::Skip { silent: false },
+                        _ => return,
+                    };
+                    if command_tx.blocking_send(command).is_err() {
+                        log::warn!("Failed to send notification action to the timer");
+                    }
+                });
+            });
+            
+            return Ok(());
+        }
+        
+        // No buttons, so nothing will send commands
+        drop(command_tx);
+        self.send_phase_change_notification(phase, status)
+    }
+    
+    pub fn send_phase_change_notification(&self, phase: &Phase, status: &Status) -> Result<(), String> {
+        let body = match &phase.description {
+            Some(description) => format!("{} ({} min)\n{}", status, phase.duration, description),
+            None => format!("{} ({} min)", status, phase.duration),
+        };
+        
+        self.send_generic_notification(&phase.name, &body)
+    }
+    
+    pub fn send_timer_completed_notification(&self, workflow: &Workflow) -> Result<(), String> {
+        self.send_generic_notification("Workflow completed", &format!("{} is done", workflow.name))
+    }
+    
+    pub fn send_day_start_notification(&self) -> Result<(), String> {
+        let yesterday = clock::now().date_naive().pred_opt();
+        let (pomodoros, minutes) = match yesterday {
+            Some(date) => history::work_summary(&history::load(), date),
+            None => (0, 0),
+        };
+        
+        self.send_generic_notification(
+            "Good morning",
+            &format!("Yesterday: {} work phases, {} minutes focused", pomodoros, minutes),
+        )
+    }
+    
+    /// Sum up the week before the current one, also logging it for those without notifications
+    pub fn send_weekly_report_notification(&self) -> Result<(), String> {
+        let this_week = history::week_start(clock::now().date_naive(), config::get().weekly_report_day);
+        let last_week = this_week - Duration::days(7);
+        let (pomodoros, minutes) = history::work_summary_between(&history::load(), last_week, this_week - Duration::days(1));
+        
+        let body = format!(
+            "Week of {}: {} work phases, {}h {:02}m focused",
+            last_week.format("%b %-d"),
+            pomodoros,
+            minutes / 60,
+            minutes % 60
+        );
+        log::info!("Weekly report: {}", body);
+        
+        self.send_generic_notification("Weekly report", &body)
+    }
+    
+    pub fn send_break_reminder_notification(&self, worked: Duration) -> Result<(), String> {
+        self.send_generic_notification(
+            "Time for a break",
+            &format!("You've been working for {} minutes without a break", worked.num_minutes()),
+        )
+    }
+    
+    /// The `warn_before_seconds` heads-up, e.g. "2 minutes left on Work"
+    pub fn send_phase_ending_notification(&self, phase: &Phase, remaining: Duration) -> Result<(), String> {
+        let seconds = remaining.num_seconds();
+        let left = match seconds {
+            60 => "1 minute".to_string(),
+            _ if seconds % 60 == 0 => format!("{} minutes", seconds / 60),
+            1 => "1 second".to_string(),
+            _ => format!("{} seconds", seconds),
+        };
+        
+        self.send_generic_notification(&format!("{} left on {}", left, phase.name), "Time to wrap up")
+    }
+    
+    pub fn send_generic_notification(&self, summary: &str, body: &str) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        
+        self.notifier.notify(summary, body)
+    }
+}
+
+// Not every notification daemon can show buttons, so ask before offering them
+#[cfg(all(feature = "notifications", unix, not(target_os = "macos")))]
+fn supports_actions() -> bool {
+    notify_rust::get_capabilities()
+        .map(|capabilities| capabilities.iter().any(|c| c == "actions"))
+        .unwrap_or(false)
+}
@@ -1,12 +1,16 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+#[cfg(any(test, feature = "bench"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+use crate::clock;
 use crate::config;
+use crate::lock::lock_or_recover;
 use crate::status::Status;
-use crate::timer::TimerState;
+use crate::timer::{StoppedSession, TimerState};
 use crate::workflow::{Phase, Workflow};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +21,28 @@ pub struct PersistentState {
     pub current_workflow: Option<Workflow>,
     pub start_time: Option<DateTime<Local>>,
     pub elapsed_seconds: u64,
+    #[serde(default)]
+    pub pause_time: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub cycle_count: u32,
+    #[serde(default)]
+    pub session_elapsed_seconds: u64,
+    #[serde(default)]
+    pub continuous_work_seconds: u64,
+    #[serde(default)]
+    pub last_start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub reps_remaining: Option<u32>,
+    #[serde(default)]
+    pub last_stopped: Option<StoppedSession>,
+    #[serde(default)]
+    pub phase_end_warned: bool,
+    #[serde(default)]
+    pub paused_seconds: u64,
+    #[serde(default)]
+    pub completed_count: u32,
+    #[serde(default)]
+    pub completed_date: Option<NaiveDate>,
     pub last_saved: DateTime<Local>,
 }
 
@@ -29,25 +55,51 @@ impl Default for PersistentState {
             current_workflow: None,
             start_time: None,
             elapsed_seconds: 0,
-            last_saved: Local::now(),
+            pause_time: None,
+            cycle_count: 0,
+            session_elapsed_seconds: 0,
+            continuous_work_seconds: 0,
+            last_start_date: None,
+            reps_remaining: None,
+            last_stopped: None,
+            phase_end_warned: false,
+            paused_seconds: 0,
+            completed_count: 0,
+            completed_date: None,
+            last_saved: clock::now(),
         }
     }
 }
 
+// How often the state was saved, for tests and `_bench` checking the save cadence
+#[cfg(any(test, feature = "bench"))]
+static UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of `update` calls so far in this process
+#[cfg(any(test, feature = "bench"))]
+pub fn update_count() -> usize {
+    UPDATES.load(Ordering::Relaxed)
+}
+
 lazy_static::lazy_static! {
     static ref STATE: Arc<Mutex<PersistentState>> = Arc::new(Mutex::new(PersistentState::default()));
 }
 
 pub fn get_state_file_path() -> PathBuf {
-    let mut path = config::get_config_dir();
+    let mut path = config::get_state_dir();
     path.push("state.json");
     path
 }
 
 pub fn init() -> Result<(), String> {
+    // Keep the default in-memory state without reading or creating the file
+    if !config::get().persistence_enabled {
+        return Ok(());
+    }
+    
     let state_path = get_state_file_path();
     
-    // Create config directory if it doesn't exist
+    // Create state directory if it doesn't exist
     if let Some(parent) = state_path.parent() {
         if !parent.exists() {
             fs::create_dir_all(parent)
@@ -71,26 +123,31 @@ pub fn init() -> Result<(), String> {
     };
     
     // Update global state
-    *STATE.lock().unwrap() = state;
+    *lock_or_recover(&STATE) = state;
     
     Ok(())
 }
 
-#[allow(dead_code)]
 pub fn get() -> PersistentState {
-    STATE.lock().unwrap().clone()
+    lock_or_recover(&STATE).clone()
 }
 
-#[allow(dead_code)]
 pub fn update(state: PersistentState) -> Result<(), String> {
+    #[cfg(any(test, feature = "bench"))]
+    UPDATES.fetch_add(1, Ordering::Relaxed);
+    
     let mut new_state = state;
-    new_state.last_saved = Local::now();
+    new_state.last_saved = clock::now();
     
-    *STATE.lock().unwrap() = new_state.clone();
+    *lock_or_recover(&STATE) = new_state.clone();
     save_state(&new_state)
 }
 
 pub fn save_state(state: &PersistentState) -> Result<(), String> {
+    if !config::get().persistence_enabled {
+        return Ok(());
+    }
+    
     let state_path = get_state_file_path();
     
     let state_str = serde_json::to_string_pretty(state)
@@ -0,0 +1,78 @@
+use log::error;
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::clock;
+use crate::timer::TimerEvent;
+
+/// Open `path` for `record`, creating it (and its directory) or appending to it
+pub fn open(path: &Path) -> Result<File, String> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory for {}: {}", path.display(), e))?;
+    }
+    
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open event log {}: {}", path.display(), e))
+}
+
+/// Append every event from `events` to `file` as a line of JSON with the time it
+/// arrived, e.g. `{"time":"…","event":"paused"}`, until the timer goes away.
+/// Events missed because the log fell behind show up as a `lagged` line
+pub async fn record(mut events: broadcast::Receiver<TimerEvent>, mut file: File) {
+    loop {
+        let record = match events.recv().await {
+            Ok(event) => event_record(&event),
+            Err(RecvError::Lagged(missed)) => json!({ "event": "lagged", "missed": missed }),
+            Err(RecvError::Closed) => return,
+        };
+        
+        let mut line = json!({ "time": clock::now().to_rfc3339() });
+        if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), record) {
+            line.extend(fields);
+        }
+        
+        // Written line by line, so the log is complete up to a crash
+        if let Err(e) = writeln!(file, "{}", line) {
+            error!("Failed to write event log, no longer recording: {}", e);
+            return;
+        }
+    }
+}
+
+// Durations go in as whole seconds, since chrono's don't serialize
+fn event_record(event: &TimerEvent) -> Value {
+    match event {
+        TimerEvent::Started { workflow, status } => json!({
+            "event": "started",
+            "workflow": workflow.name,
+            "status": status.name,
+        }),
+        TimerEvent::PhaseChanged { phase, silent } => json!({
+            "event": "phase_changed",
+            "phase": phase,
+            "silent": silent,
+        }),
+        TimerEvent::Paused => json!({ "event": "paused" }),
+        TimerEvent::Resumed => json!({ "event": "resumed" }),
+        TimerEvent::Stopped => json!({ "event": "stopped" }),
+        TimerEvent::Completed => json!({ "event": "completed" }),
+        TimerEvent::DayStarted => json!({ "event": "day_started" }),
+        TimerEvent::WeekStarted => json!({ "event": "week_started" }),
+        TimerEvent::BreakReminder { worked } => json!({
+            "event": "break_reminder",
+            "worked_seconds": worked.num_seconds(),
+        }),
+        TimerEvent::PhaseEnding { phase, remaining } => json!({
+            "event": "phase_ending",
+            "phase": phase.name,
+            "remaining_seconds": remaining.num_seconds(),
+        }),
+    }
+}
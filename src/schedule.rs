@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use log::{info, warn};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::clock;
+use crate::config;
+use crate::timer::{Timer, TimerCommand, TimerState};
+
+/// Pause armed by `schedule-pause`, as an RFC 3339 time; the daemon checks it every second
+pub fn get_pause_at_path() -> PathBuf {
+    let mut path = config::get_state_dir();
+    path.push("pause-at");
+    path
+}
+
+/// The next time the clock reads `time`: later today, or tomorrow when it's already past
+pub fn next_occurrence(time: NaiveTime) -> Result<DateTime<Local>, String> {
+    let now = clock::now();
+    let mut date = now.date_naive();
+    if time <= now.time() {
+        date += Duration::days(1);
+    }
+    
+    // A time skipped by a DST change has no local equivalent
+    date.and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| format!("{} doesn't exist on {} in the local time zone", time.format("%H:%M"), date))
+}
+
+/// Arm a pause at `at`, replacing any earlier one
+pub fn set_pause_at(at: DateTime<Local>) -> Result<(), String> {
+    let path = get_pause_at_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create state directory: {}", e))?;
+    }
+    
+    fs::write(&path, at.to_rfc3339())
+        .map_err(|e| format!("Failed to write scheduled pause: {}", e))
+}
+
+/// Disarm the scheduled pause, if there is one
+pub fn clear_pause_at() -> Result<(), String> {
+    let path = get_pause_at_path();
+    if !path.exists() {
+        return Ok(());
+    }
+    
+    fs::remove_file(&path).map_err(|e| format!("Failed to clear scheduled pause: {}", e))
+}
+
+/// When the armed pause happens, if one is armed
+pub fn pause_at() -> Option<DateTime<Local>> {
+    let content = fs::read_to_string(get_pause_at_path()).ok()?;
+    DateTime::parse_from_rfc3339(content.trim())
+        .map(|time| time.with_timezone(&Local))
+        .ok()
+}
+
+/// Pause the timer once the scheduled time comes. The pause is used up either way:
+/// when the timer isn't running by then there's nothing to pause and it's dropped
+pub async fn watch(timer: Arc<AsyncMutex<Timer>>) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+    
+    loop {
+        interval.tick().await;
+        
+        let Some(at) = pause_at() else {
+            continue;
+        };
+        if at > clock::now() {
+            continue;
+        }
+        
+        if let Err(e) = clear_pause_at() {
+            warn!("{}", e);
+        }
+        
+        let timer_lock = timer.lock().await;
+        if timer_lock.get_info().state != TimerState::Running {
+            info!("Scheduled pause at {} reached, but the timer isn't running", at.format("%H:%M"));
+            continue;
+        }
+        
+        info!("Pausing the timer as scheduled for {}", at.format("%H:%M"));
+        if let Err(e) = timer_lock.send_command(TimerCommand::Pause).await {
+            warn!("Failed to send scheduled pause to the timer: {}", e);
+        }
+    }
+}
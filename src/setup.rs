@@ -0,0 +1,78 @@
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+
+use crate::config;
+use crate::status::StatusManager;
+use crate::workflow::WorkflowManager;
+
+/// Interactive first-run setup. Only asks about settings that don't have an
+/// obviously right default; everything else keeps its current value.
+pub fn run_wizard(
+    workflow_manager: &WorkflowManager,
+    status_manager: &StatusManager,
+) -> Result<(), String> {
+    let theme = ColorfulTheme::default();
+    let mut new_config = config::get();
+
+    println!("Tomato Clock setup");
+    println!("Press Enter to keep the suggested value.\n");
+
+    let mut workflow_names: Vec<String> = workflow_manager
+        .list_workflows()
+        .into_iter()
+        .map(|workflow| workflow.name)
+        .collect();
+    workflow_names.sort();
+
+    if !workflow_names.is_empty() {
+        let current = workflow_names
+            .iter()
+            .position(|name| *name == new_config.default_workflow)
+            .unwrap_or(0);
+        let selection = Select::with_theme(&theme)
+            .with_prompt("Default workflow")
+            .items(&workflow_names)
+            .default(current)
+            .interact()
+            .map_err(|e| format!("Prompt failed: {}", e))?;
+        new_config.default_workflow = workflow_names[selection].clone();
+    }
+
+    let mut status_names: Vec<String> = status_manager
+        .list_statuses()
+        .into_iter()
+        .map(|status| status.name)
+        .collect();
+    status_names.sort();
+
+    if !status_names.is_empty() {
+        let current = status_names
+            .iter()
+            .position(|name| *name == new_config.default_status)
+            .unwrap_or(0);
+        let selection = Select::with_theme(&theme)
+            .with_prompt("Default status")
+            .items(&status_names)
+            .default(current)
+            .interact()
+            .map_err(|e| format!("Prompt failed: {}", e))?;
+        new_config.default_status = status_names[selection].clone();
+    }
+
+    new_config.waybar_integration.format = Input::with_theme(&theme)
+        .with_prompt("Waybar format ({icon}, {status}, {phase}, {remaining}, {cycle})")
+        .default(new_config.waybar_integration.format.clone())
+        .interact_text()
+        .map_err(|e| format!("Prompt failed: {}", e))?;
+
+    new_config.notification_enabled = Confirm::with_theme(&theme)
+        .with_prompt("Enable desktop notifications?")
+        .default(new_config.notification_enabled)
+        .interact()
+        .map_err(|e| format!("Prompt failed: {}", e))?;
+
+    config::update(new_config)?;
+
+    println!("\nConfiguration saved. Run `tomato-clock daemon` to start the Waybar integration.");
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+#[cfg(feature = "sound")]
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+#[cfg(feature = "sound")]
+use tokio::process::Command;
+
+use crate::config;
+use crate::workflow::{Phase, PhaseKind};
+
+/// Sounds bundled into the binary, played when a phase ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundTheme {
+    Bell,
+    Chime,
+    Digital,
+}
+
+impl SoundTheme {
+    #[cfg(feature = "sound")]
+    fn name(self) -> &'static str {
+        match self {
+            SoundTheme::Bell => "bell",
+            SoundTheme::Chime => "chime",
+            SoundTheme::Digital => "digital",
+        }
+    }
+
+    // One sound for the end of a work phase and one for the end of a break
+    #[cfg(feature = "sound")]
+    fn sound(self, kind: PhaseKind) -> &'static [u8] {
+        match (self, kind) {
+            (SoundTheme::Bell, PhaseKind::Work) => include_bytes!("../assets/sounds/bell-work.wav"),
+            (SoundTheme::Bell, PhaseKind::Break) => include_bytes!("../assets/sounds/bell-break.wav"),
+            (SoundTheme::Chime, PhaseKind::Work) => include_bytes!("../assets/sounds/chime-work.wav"),
+            (SoundTheme::Chime, PhaseKind::Break) => include_bytes!("../assets/sounds/chime-break.wav"),
+            (SoundTheme::Digital, PhaseKind::Work) => include_bytes!("../assets/sounds/digital-work.wav"),
+            (SoundTheme::Digital, PhaseKind::Break) => include_bytes!("../assets/sounds/digital-break.wav"),
+        }
+    }
+}
+
+// Set by `--sound-theme` for this run only, over `sound_theme`
+static THEME_OVERRIDE: OnceLock<SoundTheme> = OnceLock::new();
+
+/// Use `theme` for the rest of this run, whatever `sound_theme` says
+pub fn set_theme(theme: SoundTheme) {
+    let _ = THEME_OVERRIDE.set(theme);
+}
+
+/// Play the countdown tick through `sound_player`, unless notifications are off
+pub fn play_tick() {
+    let config = config::get();
+    if !config.notification_enabled {
+        return;
+    }
+    
+    play(&config.sound_player, &config.countdown_tick_sound);
+}
+
+/// Play the sound for `phase` running out: its own `sound` file when it has one,
+/// otherwise the sound theme's sound for its kind. Silent when notifications are off
+pub fn play_phase_end(phase: &Phase) {
+    let config = config::get();
+    if !config.notification_enabled {
+        return;
+    }
+    
+    if let Some(sound_file) = &phase.sound {
+        play(&config.sound_player, sound_file);
+        return;
+    }
+    
+    if let Some(theme) = THEME_OVERRIDE.get().copied().or(config.sound_theme) {
+        play_theme(&config.sound_player, theme, phase.kind());
+    }
+}
+
+// Players want a file, so the bundled sound is written out once to the runtime directory
+#[cfg(feature = "sound")]
+fn play_theme(player: &str, theme: SoundTheme, kind: PhaseKind) {
+    let kind_name = match kind {
+        PhaseKind::Work => "work",
+        PhaseKind::Break => "break",
+    };
+    let path = config::get_runtime_dir()
+        .join("sounds")
+        .join(format!("{}-{}.wav", theme.name(), kind_name));
+    
+    if !path.exists() {
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, theme.sound(kind)));
+        if let Err(e) = written {
+            warn!("Failed to write sound {}: {}", path.display(), e);
+            return;
+        }
+    }
+    
+    play(player, &path.to_string_lossy());
+}
+
+#[cfg(not(feature = "sound"))]
+fn play_theme(_player: &str, _theme: SoundTheme, _kind: PhaseKind) {}
+
+// Sounds are short, so the player runs detached and tokio reaps it
+#[cfg(feature = "sound")]
+fn play(player: &str, sound_file: &str) {
+    if let Err(e) = Command::new(player).arg(sound_file).spawn() {
+        warn!("Failed to play '{}' with {}: {}", sound_file, player, e);
+    }
+}
+
+// Built without the `sound` feature: stay silent
+#[cfg(not(feature = "sound"))]
+fn play(_player: &str, _sound_file: &str) {}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 
+use crate::lock::lock_or_recover;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Status {
     pub name: String,
@@ -47,7 +50,25 @@ impl Status {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.icon {
+            Some(icon) => write!(f, "{} {}", icon, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Why a `StatusManager` operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum StatusError {
+    #[error("Status '{0}' not found")]
+    NotFound(String),
+    #[error("Status '{0}' already exists")]
+    AlreadyExists(String),
+}
+
+#[derive(Debug, Clone)]
 pub struct StatusManager {
     statuses: Arc<Mutex<HashMap<String, Status>>>,
 }
@@ -87,10 +108,10 @@ impl StatusManager {
     }
     
     #[allow(dead_code)]
-    pub fn add_status(&self, status: Status) -> Result<(), &'static str> {
-        let mut statuses = self.statuses.lock().unwrap();
+    pub fn add_status(&self, status: Status) -> Result<(), StatusError> {
+        let mut statuses = lock_or_recover(&self.statuses);
         if statuses.contains_key(&status.name) {
-            return Err("Status with this name already exists");
+            return Err(StatusError::AlreadyExists(status.name));
         }
         
         statuses.insert(status.name.clone(), status);
@@ -98,35 +119,66 @@ impl StatusManager {
     }
     
     pub fn get_status(&self, name: &str) -> Option<Status> {
-        let statuses = self.statuses.lock().unwrap();
+        let statuses = lock_or_recover(&self.statuses);
         statuses.get(name).cloned()
     }
     
     #[allow(dead_code)]
-    pub fn remove_status(&self, name: &str) -> Result<(), &'static str> {
-        let mut statuses = self.statuses.lock().unwrap();
+    pub fn remove_status(&self, name: &str) -> Result<(), StatusError> {
+        let mut statuses = lock_or_recover(&self.statuses);
         if !statuses.contains_key(name) {
-            return Err("Status with this name does not exist");
+            return Err(StatusError::NotFound(name.to_string()));
         }
         
         statuses.remove(name);
         Ok(())
     }
     
-    #[allow(dead_code)]
     pub fn list_statuses(&self) -> Vec<Status> {
-        let statuses = self.statuses.lock().unwrap();
+        let statuses = lock_or_recover(&self.statuses);
         statuses.values().cloned().collect()
     }
     
+    /// The status after `current` in `order` (or alphabetically when `order` is empty),
+    /// wrapping around. Names in `order` that don't exist are skipped
+    pub fn next_status(&self, current: Option<&str>, order: &[String], reverse: bool) -> Option<Status> {
+        let mut cycle: Vec<Status> = if order.is_empty() {
+            let mut statuses = self.list_statuses();
+            statuses.sort_by(|a, b| a.name.cmp(&b.name));
+            statuses
+        } else {
+            order.iter().filter_map(|name| self.get_status(name)).collect()
+        };
+        
+        if reverse {
+            cycle.reverse();
+        }
+        
+        let next = current
+            .and_then(|name| cycle.iter().position(|status| status.name == name))
+            .map_or(0, |index| (index + 1) % cycle.len());
+        cycle.into_iter().nth(next)
+    }
+    
     #[allow(dead_code)]
-    pub fn update_status(&self, status: Status) -> Result<(), &'static str> {
-        let mut statuses = self.statuses.lock().unwrap();
+    pub fn update_status(&self, status: Status) -> Result<(), StatusError> {
+        let mut statuses = lock_or_recover(&self.statuses);
         if !statuses.contains_key(&status.name) {
-            return Err("Status with this name does not exist");
+            return Err(StatusError::NotFound(status.name));
         }
         
         statuses.insert(status.name.clone(), status);
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_displays_name_after_its_icon() {
+        assert_eq!(Status::new("reading").with_icon("📖").to_string(), "📖 reading");
+        assert_eq!(Status::new("reading").to_string(), "reading");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Duration, Local};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard};
+
+use crate::clock;
+use crate::config::{self, Config};
+use crate::lock::lock_or_recover;
+use crate::persistence::{self, PersistentState};
+
+// Shared setup for tests touching the global config, state, files or clock. Tests
+// using the sandbox take turns, since all of those are process-wide
+static SANDBOX: AsyncMutex<()> = AsyncMutex::const_new(());
+static SANDBOX_DIR: OnceLock<PathBuf> = OnceLock::new();
+static FAKE_NOW: Mutex<Option<DateTime<Local>>> = Mutex::new(None);
+
+/// Take the sandbox for the rest of the test: an empty profile directory for every
+/// file, the default config with notifications off, a fresh state and the real clock
+pub async fn sandbox() -> MutexGuard<'static, ()> {
+    let guard = SANDBOX.lock().await;
+    
+    let dir = SANDBOX_DIR.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("tomato-clock-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create sandbox directory");
+        config::init(Some(dir.clone())).expect("use sandbox directory as profile");
+        dir
+    });
+    
+    for entry in fs::read_dir(dir).expect("read sandbox directory").flatten() {
+        let path = entry.path();
+        let _ = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+    }
+    
+    config::set(Config {
+        notification_enabled: false,
+        ..Config::default()
+    });
+    *lock_or_recover(&FAKE_NOW) = None;
+    clock::set_source(Arc::new(|| lock_or_recover(&FAKE_NOW).unwrap_or_else(Local::now)));
+    persistence::update(PersistentState::default()).expect("reset state");
+    
+    guard
+}
+
+/// Make `clock::now` read `time` until the clock is set or advanced again
+pub fn set_clock(time: DateTime<Local>) {
+    *lock_or_recover(&FAKE_NOW) = Some(time);
+}
+
+/// Move the clock set by `set_clock` (or the real time) on by `by`, which may be negative
+pub fn advance_clock(by: Duration) {
+    let mut now = lock_or_recover(&FAKE_NOW);
+    *now = Some(now.unwrap_or_else(Local::now) + by);
+}
@@ -1,12 +1,35 @@
-use chrono::{DateTime, Duration, Local};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use tokio::sync::{broadcast, mpsc};
+use tokio::sync::Mutex as AsyncMutex;
 use tokio::time;
 
+use crate::clock;
+use crate::config::{self, ResumeMode};
+use crate::history::{self, HistoryEntry};
+use crate::hooks::{self, HookEvent};
+use crate::lock::{read_or_recover, write_or_recover};
+use crate::notification::NotificationService;
 use crate::status::Status;
 use crate::workflow::{Phase, Workflow};
 use crate::persistence;
+use crate::sound;
+use crate::tts;
+
+/// Ticks a `Running` timer may spend without a phase or remaining time before
+/// the watchdog resets it to `Idle`
+const WATCHDOG_MAX_INCONSISTENT_TICKS: u32 = 3;
+
+/// How far the wall clock may go backwards between ticks before it counts as a
+/// step (e.g. an NTP correction) and the timer's timestamps are re-anchored
+const CLOCK_STEP_BACK_TOLERANCE_SECONDS: i64 = 1;
+
+/// Panics the timer task may recover from before the process gives up and exits,
+/// leaving the restart to a supervisor such as systemd
+const MAX_TIMER_TASK_RESTARTS: u32 = 5;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TimerState {
@@ -14,18 +37,121 @@ pub enum TimerState {
     Running,
     Paused,
     Completed,
+    /// The next phase is ready but waits for `start` or `resume` to begin
+    WaitingForStart,
+}
+
+impl TimerState {
+    /// The name scripts see in `info --porcelain` and `info --field state`; part of
+    /// the porcelain contract, so these never change
+    pub fn porcelain_name(&self) -> &'static str {
+        match self {
+            TimerState::Idle => "Idle",
+            TimerState::Running => "Running",
+            TimerState::Paused => "Paused",
+            TimerState::Completed => "Completed",
+            TimerState::WaitingForStart => "WaitingForStart",
+        }
+    }
+}
+
+impl fmt::Display for TimerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            TimerState::Idle => "Idle",
+            TimerState::Running => "Running",
+            TimerState::Paused => "Paused",
+            TimerState::Completed => "Completed",
+            TimerState::WaitingForStart => "Waiting for start",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Serialize a `Duration` as whole seconds so the JSON contract doesn't depend
+/// on chrono's internal representation
+pub mod duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        i64::deserialize(deserializer).map(Duration::seconds)
+    }
+}
+
+/// Same as [`duration_seconds`] for optional durations, `None` becomes `null`
+pub mod option_duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.num_seconds()),
+            None => serializer.serialize_none(),
+        }
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Option::<i64>::deserialize(deserializer).map(|seconds| seconds.map(Duration::seconds))
+    }
+}
+
+/// Where a session was when `stop` ended it, picked up again by `start --continue`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoppedSession {
+    pub workflow: String,
+    pub phase: String,
+    #[serde(with = "duration_seconds")]
+    pub elapsed: Duration,
+    #[serde(default)]
+    pub reps_remaining: Option<u32>,
+    pub cycle_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimerInfo {
     pub state: TimerState,
     pub current_phase: Option<Phase>,
+    #[serde(with = "option_duration_seconds")]
     pub time_remaining: Option<Duration>,
+    #[serde(with = "duration_seconds")]
     pub elapsed_time: Duration,
     pub current_status: Option<Status>,
-    pub current_workflow: Option<Workflow>,
+    // Shared so the frequent `get_info` snapshots don't copy every phase
+    pub current_workflow: Option<Arc<Workflow>>,
     pub start_time: Option<DateTime<Local>>,
     pub pause_time: Option<DateTime<Local>>,
+    /// Ordinal of the current work phase in this session, starting at 1
+    pub cycle_count: u32,
+    /// Time spent running across all phases since `start`, excluding pauses
+    #[serde(with = "duration_seconds")]
+    pub session_elapsed: Duration,
+    /// Work time since the last break phase, for `max_continuous_work_minutes`
+    #[serde(with = "duration_seconds")]
+    pub continuous_work: Duration,
+    /// Local day of the last `start`, to spot the first one of a new day
+    pub last_start_date: Option<NaiveDate>,
+    /// Reps left in a rep-based phase, which has no `time_remaining`
+    #[serde(default)]
+    pub reps_remaining: Option<u32>,
+    /// The phase the last `stop` interrupted
+    #[serde(default)]
+    pub last_stopped: Option<StoppedSession>,
+    /// The `warn_before_seconds` notification for this phase has gone out
+    #[serde(default)]
+    pub phase_end_warned: bool,
+    /// Time the current phase spent paused before the latest resume
+    #[serde(default, with = "duration_seconds")]
+    pub paused_time: Duration,
+    /// Work phases finished on `completed_date`, for `{today_count}`
+    #[serde(default)]
+    pub completed_count: u32,
+    #[serde(default)]
+    pub completed_date: Option<NaiveDate>,
 }
 
 impl Default for TimerInfo {
@@ -39,6 +165,35 @@ impl Default for TimerInfo {
             current_workflow: None,
             start_time: None,
             pause_time: None,
+            cycle_count: 0,
+            session_elapsed: Duration::zero(),
+            continuous_work: Duration::zero(),
+            last_start_date: None,
+            reps_remaining: None,
+            last_stopped: None,
+            phase_end_warned: false,
+            paused_time: Duration::zero(),
+            completed_count: 0,
+            completed_date: None,
+        }
+    }
+}
+
+impl TimerInfo {
+    /// Time the current phase has spent paused, counting a pause still going on
+    pub fn phase_paused(&self) -> Duration {
+        let ongoing = match (&self.state, self.pause_time) {
+            (TimerState::Paused, Some(pause_time)) => (clock::now() - pause_time).max(Duration::zero()),
+            _ => Duration::zero(),
+        };
+        self.paused_time + ongoing
+    }
+    
+    /// Work phases finished today, zero when none were since midnight
+    pub fn today_count(&self) -> u32 {
+        match self.completed_date {
+            Some(date) if date == clock::now().date_naive() => self.completed_count,
+            _ => 0,
         }
     }
 }
@@ -48,41 +203,80 @@ pub enum TimerCommand {
     Start {
         workflow: Option<Workflow>,
         status: Option<Status>,
+        /// Pick up the phase the last `stop` interrupted when it belongs to this workflow
+        continue_session: bool,
     },
     Pause,
+    /// Pause because the session went idle `idle_for` ago, taking that time back from the phase
+    PauseIdle {
+        idle_for: Duration,
+    },
     Resume,
-    Stop,
-    Skip,
+    /// Count one rep of a rep-based phase, finishing it after the last
+    Rep,
+    Stop {
+        /// Log the unfinished phase to history as interrupted
+        save_session: bool,
+    },
+    Skip {
+        /// Change phase without a notification
+        silent: bool,
+    },
+    /// Go back to the phase before the current one
+    Previous {
+        /// Resume it near its end instead of from the start
+        keep_progress: bool,
+        silent: bool,
+    },
+    SetStatus {
+        status: Status,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TimerEvent {
     Started {
-        #[allow(dead_code)]
         workflow: Workflow,
-        #[allow(dead_code)]
         status: Status,
     },
     PhaseChanged {
-        #[allow(dead_code)]
         phase: Phase,
+        /// A deliberate skip asked not to be notified about
+        silent: bool,
     },
     Paused,
     Resumed,
     Stopped,
     Completed,
+    /// The first start since local midnight
+    DayStarted,
+    /// The first start since the week began on `weekly_report_day`
+    WeekStarted,
+    BreakReminder {
+        worked: Duration,
+    },
+    /// `warn_before_seconds` are left in the phase
+    PhaseEnding {
+        phase: Phase,
+        remaining: Duration,
+    },
 }
 
 pub struct Timer {
-    info: Arc<Mutex<TimerInfo>>,
+    info: Arc<RwLock<TimerInfo>>,
     command_tx: mpsc::Sender<TimerCommand>,
+    // Every event the consumer handles, passed on for `subscribe`
+    events: broadcast::Sender<TimerEvent>,
     // Keep a channel for events but mark it as unused to suppress warnings
     #[allow(dead_code)]
     event_rx: mpsc::Receiver<TimerEvent>,
 }
 
 impl Timer {
-    pub async fn new() -> Self {
+    /// Create a timer from the persisted state. A session that was `Running` is
+    /// handled per `on_startup`: the daemon passes `resume_on_startup`, while
+    /// one-off commands pass `ResumeMode::Resume` so they see the state as it is
+    pub async fn new(on_startup: ResumeMode) -> Self {
         let (command_tx, command_rx) = mpsc::channel(100);
         let (event_tx, event_rx) = mpsc::channel(100);
         
@@ -90,20 +284,34 @@ impl Timer {
         let persisted_state = persistence::get();
         
         // Create initial timer info from persisted state
-        let mut timer_info = TimerInfo {
-            state: persisted_state.timer_state,
-            current_phase: persisted_state.current_phase.clone(),
-            time_remaining: None, // We'll recalculate this if needed
-            elapsed_time: Duration::seconds(persisted_state.elapsed_seconds as i64),
-            current_status: persisted_state.current_status.clone(),
-            current_workflow: persisted_state.current_workflow.clone(),
-            start_time: persisted_state.start_time,
-            pause_time: None, // We don't persist pause time
-        };
+        let mut timer_info = TimerInfo::from(persisted_state);
+        
+        // A completed cycle from a previous run shouldn't linger on the bar
+        if timer_info.state == TimerState::Completed && config::get().reset_completed_on_startup {
+            timer_info.state = TimerState::Idle;
+        }
+        
+        if timer_info.state == TimerState::Running && on_startup != ResumeMode::Resume {
+            if on_startup == ResumeMode::Pause {
+                info!("Restoring the running session paused, resume it to carry on");
+                timer_info.state = TimerState::Paused;
+                timer_info.pause_time = Some(clock::now());
+            } else {
+                info!("Stopping the running session from before, `start --continue` picks it up");
+                remember_stopped_session(&mut timer_info);
+                reset_to_idle(&mut timer_info);
+            }
+            save_timer_state(&timer_info);
+        }
         
         // Calculate time_remaining based on current phase and elapsed time
-        if timer_info.state == TimerState::Running && timer_info.current_phase.is_some() {
-            let phase = timer_info.current_phase.as_ref().unwrap();
+        let active = matches!(
+            timer_info.state,
+            TimerState::Running | TimerState::Paused | TimerState::WaitingForStart
+        );
+        // Rep-based phases count reps instead, restored as they were
+        let timed_phase = timer_info.current_phase.as_ref().filter(|phase| active && phase.reps.is_none());
+        if let Some(phase) = timed_phase {
             let total_duration = Duration::minutes(phase.duration as i64);
             let elapsed = timer_info.elapsed_time;
             
@@ -115,32 +323,55 @@ impl Timer {
             }
         }
         
-        let info = Arc::new(Mutex::new(timer_info));
+        let info = Arc::new(RwLock::new(timer_info));
         
-        // Spawn timer logic task with a cloned event sender
+        // Spawn timer logic task under a supervisor that restarts it if it dies
         let timer_info_clone = Arc::clone(&info);
         
         tokio::spawn(async move {
-            timer_logic_task(timer_info_clone, command_rx, event_tx).await;
+            supervise_timer_task(timer_info_clone, command_rx, event_tx).await;
         });
         
         // Spawn a task to consume events so they don't pile up
+        let timer_info_clone = Arc::clone(&info);
+        let command_tx_clone = command_tx.clone();
+        let (events, _) = broadcast::channel(100);
+        let events_clone = events.clone();
         tokio::spawn(async move {
-            event_consumer_task(event_rx).await;
+            event_consumer_task(timer_info_clone, event_rx, command_tx_clone, events_clone).await;
         });
         
         Timer {
             info: Arc::clone(&info),
             command_tx,
+            events,
             event_rx: mpsc::channel(100).1,  // Create a dummy receiver
         }
     }
     
+    /// Receive the timer's events from now on, e.g. to print phase changes
+    pub fn subscribe(&self) -> broadcast::Receiver<TimerEvent> {
+        self.events.subscribe()
+    }
+    
     pub fn get_info(&self) -> TimerInfo {
-        self.info.lock().unwrap().clone()
+        read_or_recover(&self.info).clone()
+    }
+    
+    /// Write the current state to disk right away instead of waiting for the next periodic save
+    pub fn save_state(&self) {
+        save_timer_state(&read_or_recover(&self.info));
     }
     
     pub async fn send_command(&self, command: TimerCommand) -> Result<(), &'static str> {
+        // An empty workflow would leave the timer Running with nothing to count down
+        if let TimerCommand::Start { workflow: Some(workflow), .. } = &command {
+            if workflow.phases.is_empty() {
+                error!("Workflow '{}' has no phases, refusing to start it", workflow.name);
+                return Err("Workflow has no phases");
+            }
+        }
+        
         self.command_tx.send(command).await.map_err(|_| "Failed to send command")
     }
     
@@ -151,48 +382,201 @@ impl Timer {
     }
 }
 
+// Run the timer logic task, restarting it when it panics or stops unexpectedly.
+// The command receiver lives behind an async mutex so it survives a restart.
+async fn supervise_timer_task(
+    timer_info: Arc<RwLock<TimerInfo>>,
+    command_rx: mpsc::Receiver<TimerCommand>,
+    event_tx: mpsc::Sender<TimerEvent>,
+) {
+    let command_rx = Arc::new(AsyncMutex::new(command_rx));
+    let mut panics: u32 = 0;
+    
+    loop {
+        let task = tokio::spawn(timer_logic_task(
+            Arc::clone(&timer_info),
+            Arc::clone(&command_rx),
+            event_tx.clone(),
+        ));
+        
+        match task.await {
+            Ok(()) => warn!("Timer task stopped unexpectedly, restarting it"),
+            Err(e) if e.is_panic() => {
+                panics += 1;
+                if panics > MAX_TIMER_TASK_RESTARTS {
+                    error!("Timer task panicked {} times, exiting", panics);
+                    std::process::exit(1);
+                }
+                error!("Timer task panicked, restarting it ({}/{})", panics, MAX_TIMER_TASK_RESTARTS);
+                time::sleep(time::Duration::from_secs(1)).await;
+            }
+            Err(_) => return, // Cancelled, the runtime is shutting down
+        }
+    }
+}
+
 async fn timer_logic_task(
-    timer_info: Arc<Mutex<TimerInfo>>,
-    mut command_rx: mpsc::Receiver<TimerCommand>,
+    timer_info: Arc<RwLock<TimerInfo>>,
+    command_rx: Arc<AsyncMutex<mpsc::Receiver<TimerCommand>>>,
     event_tx: mpsc::Sender<TimerEvent>,
 ) {
+    let mut command_rx = command_rx.lock().await;
     let mut interval = time::interval(time::Duration::from_secs(1));
+    let mut inconsistent_ticks: u32 = 0;
+    let mut last_tick_time = clock::now();
     
     loop {
         tokio::select! {
             _ = interval.tick() => {
+                // Phases count ticks, but the timestamps kept alongside follow the wall clock;
+                // move them back with it so they don't end up in the future
+                let now = clock::now();
+                let step_back = last_tick_time - now;
+                last_tick_time = now;
+                if step_back > Duration::seconds(CLOCK_STEP_BACK_TOLERANCE_SECONDS) {
+                    warn!("System clock went back by {} seconds, re-anchoring the timer", step_back.num_seconds());
+                    let mut info = write_or_recover(&timer_info);
+                    reanchor_timestamps(&mut info, step_back);
+                    save_timer_state(&info);
+                }
+                
+                // Watchdog: a Running timer with no phase or no remaining time never advances.
+                // Checking only needs a read lock; the write lock is for the reset
+                let stuck = {
+                    let info = read_or_recover(&timer_info);
+                    info.state == TimerState::Running
+                        && (info.current_phase.is_none()
+                            || (info.time_remaining.is_none() && info.reps_remaining.is_none()))
+                };
+                if stuck {
+                    inconsistent_ticks += 1;
+                } else {
+                    inconsistent_ticks = 0;
+                }
+                
+                if inconsistent_ticks > WATCHDOG_MAX_INCONSISTENT_TICKS {
+                    let mut info = write_or_recover(&timer_info);
+                    warn!(
+                        "Timer stuck in Running without {} for {} ticks, resetting to Idle",
+                        if info.current_phase.is_none() { "a phase" } else { "remaining time" },
+                        inconsistent_ticks
+                    );
+                    info.state = TimerState::Idle;
+                    info.current_phase = None;
+                    info.time_remaining = None;
+                    info.pause_time = None;
+                    inconsistent_ticks = 0;
+                    
+                    // Save state after recovery
+                    save_timer_state(&info);
+                    continue;
+                }
+                
                 // Update timer if running
                 let mut update_needed = false;
+                let mut break_reminder = None;
+                let mut countdown_tick = false;
+                let mut phase_ending = None;
+                let mut finished_phase = None;
                 {
-                    let mut info = timer_info.lock().unwrap();
-                    if info.state == TimerState::Running {
-                        if let Some(mut remaining) = info.time_remaining {
+                    let mut info = write_or_recover(&timer_info);
+                    // Timed phases count down, rep-based ones only up
+                    let counting = info.time_remaining.is_some() || info.reps_remaining.is_some();
+                    if info.state == TimerState::Running && counting {
+                        info.session_elapsed += Duration::seconds(1);
+                        
+                        // Nudge towards a break after every `max_continuous_work_minutes` of work
+                        if info.current_phase.as_ref().is_some_and(|phase| !phase.is_break()) {
+                            info.continuous_work += Duration::seconds(1);
+                            
+                            if let Some(max_minutes) = config::get().max_continuous_work_minutes.filter(|m| *m > 0) {
+                                let worked = info.continuous_work.num_seconds();
+                                if worked % (max_minutes as i64 * 60) == 0 {
+                                    break_reminder = Some(info.continuous_work);
+                                }
+                            }
+                        }
+                        
+                        if info.reps_remaining.is_some() {
+                            // Rep-based phases only count up until `rep` finishes them
+                            info.elapsed_time += Duration::seconds(1);
+                            
+                            if info.elapsed_time.num_seconds() % 10 == 0 {
+                                save_timer_state(&info);
+                            }
+                        } else if let Some(mut remaining) = info.time_remaining.filter(|r| *r > Duration::seconds(1)) {
                             // Decrease remaining time
-                            if remaining > Duration::seconds(1) {
-                                remaining = remaining - Duration::seconds(1);
-                                info.time_remaining = Some(remaining);
-                                info.elapsed_time = info.elapsed_time + Duration::seconds(1);
-                                
-                                // Save state every 10 seconds to avoid too frequent writes
-                                if info.elapsed_time.num_seconds() % 10 == 0 {
+                            remaining = remaining - Duration::seconds(1);
+                            info.time_remaining = Some(remaining);
+                            
+                            // Audible countdown; stops by itself once paused or skipped
+                            if let Some(tick_seconds) = config::get().countdown_tick_seconds {
+                                countdown_tick = remaining.num_seconds() <= tick_seconds as i64;
+                            }
+                            
+                            // Warn once per phase, and only in phases long enough to cross the threshold
+                            if let (Some(warn_seconds), Some(phase)) = (config::get().warn_before_seconds, &info.current_phase) {
+                                let threshold = Duration::seconds(warn_seconds as i64);
+                                if !info.phase_end_warned
+                                    && remaining <= threshold
+                                    && Duration::minutes(phase.duration as i64) > threshold
+                                {
+                                    phase_ending = Some(TimerEvent::PhaseEnding { phase: phase.clone(), remaining });
+                                    info.phase_end_warned = true;
                                     save_timer_state(&info);
                                 }
-                            } else {
-                                // Phase completed
-                                info.time_remaining = None;
-                                update_needed = true;
-                                
-                                // Save state on phase completion
+                            }
+                            info.elapsed_time = info.elapsed_time + Duration::seconds(1);
+                            
+                            // Save state every 10 seconds to avoid too frequent writes
+                            if info.elapsed_time.num_seconds() % 10 == 0 {
                                 save_timer_state(&info);
                             }
+                        } else {
+                            // Phase completed
+                            let remaining = info.time_remaining.unwrap_or_else(Duration::zero);
+                            info.elapsed_time += remaining;
+                            info.time_remaining = None;
+                            update_needed = true;
+                            finished_phase = info.current_phase.clone();
+                            count_completed(&mut info);
+                            
+                            if let Some(entry) = HistoryEntry::from_timer(&info, false) {
+                                if let Err(e) = history::record(&entry) {
+                                    error!("Failed to record history: {}", e);
+                                }
+                            }
+                            
+                            // Save state on phase completion
+                            save_timer_state(&info);
                         }
                     }
                 }
                 
+                if countdown_tick {
+                    sound::play_tick();
+                }
+                
+                if let Some(phase) = &finished_phase {
+                    sound::play_phase_end(phase);
+                }
+                
+                if let Some(event) = phase_ending {
+                    if event_tx.send(event).await.is_err() {
+                        warn!("Failed to send phase ending event");
+                    }
+                }
+                
+                if let Some(worked) = break_reminder {
+                    if event_tx.send(TimerEvent::BreakReminder { worked }).await.is_err() {
+                        warn!("Failed to send break reminder event");
+                    }
+                }
+                
                 if update_needed {
                     // Handle phase transition logic here
-                    let phase_completed = {
-                        let mut info = timer_info.lock().unwrap();
+                    let event = {
+                        let mut info = write_or_recover(&timer_info);
                         let workflow_opt = info.current_workflow.clone();
                         let current_phase_opt = info.current_phase.clone();
                         
@@ -203,27 +587,23 @@ async fn timer_logic_task(
                                 if current_index + 1 < workflow.phases.len() {
                                     // Move to the next phase
                                     let next_phase = workflow.phases[current_index + 1].clone();
-                                    info.current_phase = Some(next_phase.clone());
-                                    info.time_remaining = Some(Duration::minutes(next_phase.duration as i64));
-                                    info.elapsed_time = Duration::zero();
+                                    begin_phase(&mut info, &next_phase);
+                                    hold_if_manual_advance(&mut info, &current_phase);
                                     
                                     // Save state after phase transition
                                     save_timer_state(&info);
                                     
-                                    // Return the phase for the event
-                                    next_phase
+                                    TimerEvent::PhaseChanged { phase: next_phase, silent: false }
                                 } else if workflow.repeatable {
                                     // If workflow is repeatable, start over
                                     let next_phase = workflow.phases[0].clone();
-                                    info.current_phase = Some(next_phase.clone());
-                                    info.time_remaining = Some(Duration::minutes(next_phase.duration as i64));
-                                    info.elapsed_time = Duration::zero();
+                                    begin_phase(&mut info, &next_phase);
+                                    hold_if_manual_advance(&mut info, &current_phase);
                                     
                                     // Save state after phase transition
                                     save_timer_state(&info);
                                     
-                                    // Return the phase for the event
-                                    next_phase
+                                    TimerEvent::PhaseChanged { phase: next_phase, silent: false }
                                 } else {
                                     // End of workflow
                                     info.state = TimerState::Completed;
@@ -233,7 +613,7 @@ async fn timer_logic_task(
                                     // Save state after completion
                                     save_timer_state(&info);
                                     
-                                    return;
+                                    TimerEvent::Completed
                                 }
                             } else {
                                 // This shouldn't happen, but just in case
@@ -244,7 +624,7 @@ async fn timer_logic_task(
                                 // Save state after reset
                                 save_timer_state(&info);
                                 
-                                return;
+                                continue;
                             }
                         } else {
                             // No workflow or phase
@@ -253,23 +633,24 @@ async fn timer_logic_task(
                             // Save state after reset
                             save_timer_state(&info);
                             
-                            return;
+                            continue;
                         }
                     };
                     
-                    // Send phase changed event after releasing the lock
-                    let send_result = event_tx.send(TimerEvent::PhaseChanged {
-                        phase: phase_completed,
-                    }).await;
+                    // Send the event after releasing the lock
+                    let send_result = event_tx.send(event).await;
                     if send_result.is_err() {
-                        println!("Failed to send phase changed event");
+                        warn!("Failed to send phase changed event");
                     }
                 }
             }
             
             Some(command) = command_rx.recv() => {
                 match command {
-                    TimerCommand::Start { workflow, status } => {
+                    TimerCommand::Start { workflow, status, continue_session } => {
+                        let first_start_today;
+                        let first_start_this_week;
+                        
                         // Start timer logic
                         let event = {
                             // Create local variables before we take the lock
@@ -283,23 +664,40 @@ async fn timer_logic_task(
                                 Status::default()
                             });
                             
-                            // Prepare the initial phase if there is one
-                            let initial_phase = workflow_to_use.phases.first().cloned();
+                            // Without a first phase there's nothing to run; `send_command` rejects
+                            // these, this catches commands that got here another way
+                            let Some(initial_phase) = workflow_to_use.phases.first().cloned() else {
+                                warn!("Workflow '{}' has no phases, not starting", workflow_to_use.name);
+                                continue;
+                            };
                             
                             // Now take the lock and update
-                            let mut info = timer_info.lock().unwrap();
+                            let mut info = write_or_recover(&timer_info);
                             
-                            // Set initial phase
-                            if let Some(phase) = &initial_phase {
-                                info.current_phase = Some(phase.clone());
-                                info.time_remaining = Some(Duration::minutes(phase.duration as i64));
-                            }
+                            // Set initial phase, counting cycles afresh for the new session
+                            info.cycle_count = 0;
+                            begin_phase(&mut info, &initial_phase);
                             
-                            info.current_workflow = Some(workflow_to_use.clone());
+                            info.current_workflow = Some(Arc::new(workflow_to_use.clone()));
                             info.current_status = Some(status_to_use.clone());
                             info.state = TimerState::Running;
-                            info.start_time = Some(Local::now());
+                            info.start_time = Some(clock::now());
                             info.elapsed_time = Duration::zero();
+                            info.session_elapsed = Duration::zero();
+                            info.continuous_work = Duration::zero();
+                            
+                            // Last, so the phase and elapsed time it restores aren't reset
+                            if continue_session {
+                                continue_stopped_session(&mut info, &workflow_to_use);
+                            }
+                            
+                            let today = clock::now().date_naive();
+                            first_start_today = info.last_start_date != Some(today);
+                            
+                            // A first ever start has no previous week to look back on
+                            let week_start = history::week_start(today, config::get().weekly_report_day);
+                            first_start_this_week = info.last_start_date.is_some_and(|last| last < week_start);
+                            info.last_start_date = Some(today);
                             
                             // Save state after starting
                             save_timer_state(&info);
@@ -314,7 +712,16 @@ async fn timer_logic_task(
                         // Send event after releasing the lock
                         let send_result = event_tx.send(event).await;
                         if send_result.is_err() {
-                            println!("Failed to send start event");
+                            warn!("Failed to send start event");
+                        }
+                        
+                        if first_start_today && event_tx.send(TimerEvent::DayStarted).await.is_err() {
+                            warn!("Failed to send day start event");
+                        }
+                        
+                        let weekly_report = first_start_this_week && config::get().weekly_report;
+                        if weekly_report && event_tx.send(TimerEvent::WeekStarted).await.is_err() {
+                            warn!("Failed to send week start event");
                         }
                     }
                     
@@ -323,11 +730,11 @@ async fn timer_logic_task(
                         let should_pause;
                         let mut paused_info = None;
                         {
-                            let mut info = timer_info.lock().unwrap();
+                            let mut info = write_or_recover(&timer_info);
                             should_pause = info.state == TimerState::Running;
                             if should_pause {
                                 info.state = TimerState::Paused;
-                                info.pause_time = Some(Local::now());
+                                info.pause_time = Some(clock::now());
                                 
                                 // Save state after pausing
                                 save_timer_state(&info);
@@ -341,24 +748,55 @@ async fn timer_logic_task(
                         if should_pause {
                             // Ensure the state is properly persisted
                             if let Some(info) = paused_info {
-                                *timer_info.lock().unwrap() = info;
+                                *write_or_recover(&timer_info) = info;
                             }
                             
                             let send_result = event_tx.send(TimerEvent::Paused).await;
                             if send_result.is_err() {
-                                println!("Failed to send pause event");
+                                warn!("Failed to send pause event");
                             }
                         }
                     }
                     
+                    TimerCommand::PauseIdle { idle_for } => {
+                        {
+                            let mut info = write_or_recover(&timer_info);
+                            if info.state != TimerState::Running {
+                                continue;
+                            }
+                            
+                            // The timer kept counting while the session sat idle; give that
+                            // back, but never past the start of the current phase
+                            let rewind = idle_for.clamp(Duration::zero(), info.elapsed_time);
+                            info.elapsed_time -= rewind;
+                            info.session_elapsed = (info.session_elapsed - rewind).max(Duration::zero());
+                            info.continuous_work = (info.continuous_work - rewind).max(Duration::zero());
+                            if let Some(remaining) = info.time_remaining {
+                                info.time_remaining = Some(remaining + rewind);
+                            }
+                            
+                            info.state = TimerState::Paused;
+                            info.pause_time = Some(clock::now() - rewind);
+                            
+                            // Save state after pausing
+                            save_timer_state(&info);
+                        }
+                        
+                        let send_result = event_tx.send(TimerEvent::Paused).await;
+                        if send_result.is_err() {
+                            warn!("Failed to send pause event");
+                        }
+                    }
+                    
                     TimerCommand::Resume => {
                         // We'll prepare the event outside the lock
                         let should_resume;
                         let mut resumed_info = None;
                         {
-                            let mut info = timer_info.lock().unwrap();
-                            should_resume = info.state == TimerState::Paused;
+                            let mut info = write_or_recover(&timer_info);
+                            should_resume = matches!(info.state, TimerState::Paused | TimerState::WaitingForStart);
                             if should_resume {
+                                info.paused_time = info.phase_paused();
                                 info.state = TimerState::Running;
                                 info.pause_time = None;
                                 
@@ -374,25 +812,100 @@ async fn timer_logic_task(
                         if should_resume {
                             // Ensure the state is properly persisted
                             if let Some(info) = resumed_info {
-                                *timer_info.lock().unwrap() = info;
+                                *write_or_recover(&timer_info) = info;
                             }
                             
                             let send_result = event_tx.send(TimerEvent::Resumed).await;
                             if send_result.is_err() {
-                                println!("Failed to send resume event");
+                                warn!("Failed to send resume event");
                             }
                         }
                     }
                     
-                    TimerCommand::Stop => {
+                    TimerCommand::Rep => {
+                        let (event, finished_phase) = {
+                            let mut info = write_or_recover(&timer_info);
+                            if !matches!(info.state, TimerState::Running | TimerState::Paused) {
+                                continue;
+                            }
+                            let Some(reps_left) = info.reps_remaining else {
+                                warn!("The current phase isn't rep-based, ignoring rep");
+                                continue;
+                            };
+                            
+                            if reps_left > 1 {
+                                info.reps_remaining = Some(reps_left - 1);
+                                
+                                // Save state after counting the rep
+                                save_timer_state(&info);
+                                continue;
+                            }
+                            
+                            // The last rep finishes the phase like a timed one running out
+                            count_completed(&mut info);
+                            if let Some(entry) = HistoryEntry::from_timer(&info, false) {
+                                if let Err(e) = history::record(&entry) {
+                                    error!("Failed to record history: {}", e);
+                                }
+                            }
+                            
+                            let (Some(workflow), Some(current_phase)) = (info.current_workflow.clone(), info.current_phase.clone()) else {
+                                continue;
+                            };
+                            
+                            match phase_after(&workflow, &current_phase) {
+                                Some(next_phase) => {
+                                    begin_phase(&mut info, &next_phase);
+                                    info.state = TimerState::Running;
+                                    info.pause_time = None;
+                                    hold_if_manual_advance(&mut info, &current_phase);
+                                    
+                                    // Save state after phase transition
+                                    save_timer_state(&info);
+                                    
+                                    (TimerEvent::PhaseChanged { phase: next_phase, silent: false }, current_phase)
+                                }
+                                None => {
+                                    info.state = TimerState::Completed;
+                                    info.current_phase = None;
+                                    info.time_remaining = None;
+                                    info.reps_remaining = None;
+                                    
+                                    // Save state after completion
+                                    save_timer_state(&info);
+                                    
+                                    (TimerEvent::Completed, current_phase)
+                                }
+                            }
+                        };
+                        
+                        sound::play_phase_end(&finished_phase);
+                        
+                        // Send event after releasing the lock
+                        if event_tx.send(event).await.is_err() {
+                            warn!("Failed to send rep event");
+                        }
+                    }
+                    
+                    TimerCommand::Stop { save_session } => {
                         // Update timer state
                         {
-                            let mut info = timer_info.lock().unwrap();
-                            info.state = TimerState::Idle;
-                            info.current_phase = None;
-                            info.time_remaining = None;
-                            info.start_time = None;
-                            info.pause_time = None;
+                            let mut info = write_or_recover(&timer_info);
+                            
+                            // Keep the focused time of a phase cut short
+                            let active = matches!(info.state, TimerState::Running | TimerState::Paused);
+                            if active && (save_session || config::get().log_interrupted) {
+                                if let Some(entry) = HistoryEntry::from_timer(&info, true) {
+                                    if let Err(e) = history::record(&entry) {
+                                        error!("Failed to record history: {}", e);
+                                    }
+                                }
+                            }
+                            
+                            if active {
+                                remember_stopped_session(&mut info);
+                            }
+                            reset_to_idle(&mut info);
                             
                             // Save state after stopping
                             save_timer_state(&info);
@@ -401,18 +914,26 @@ async fn timer_logic_task(
                         // Send event after releasing the lock
                         let send_result = event_tx.send(TimerEvent::Stopped).await;
                         if send_result.is_err() {
-                            println!("Failed to send stop event");
+                            warn!("Failed to send stop event");
                         }
                     }
                     
-                    TimerCommand::Skip => {
+                    TimerCommand::SetStatus { status } => {
+                        let mut info = write_or_recover(&timer_info);
+                        info.current_status = Some(status);
+                        
+                        // Save state after relabeling
+                        save_timer_state(&info);
+                    }
+                    
+                    TimerCommand::Skip { silent } => {
                         // Implement skip logic - clone data first to avoid borrow issues
                         let (workflow_opt, phase_opt, is_running_or_paused) = {
-                            let info = timer_info.lock().unwrap();
+                            let info = read_or_recover(&timer_info);
                             (
                                 info.current_workflow.clone(),
                                 info.current_phase.clone(), 
-                                info.state == TimerState::Running || info.state == TimerState::Paused
+                                matches!(info.state, TimerState::Running | TimerState::Paused | TimerState::WaitingForStart)
                             )
                         };
                         
@@ -420,6 +941,11 @@ async fn timer_logic_task(
                             continue;
                         }
                         
+                        if phase_opt.as_ref().is_some_and(|phase| !phase.skippable) {
+                            warn!("Phase can't be skipped, ignoring skip");
+                            continue;
+                        }
+                        
                         if let (Some(workflow), Some(current_phase)) = (workflow_opt, phase_opt) {
                             // Find the current phase index
                             if let Some(current_index) = workflow.phases.iter().position(|p| p.name == current_phase.name) {
@@ -430,11 +956,9 @@ async fn timer_logic_task(
                                     
                                     // Update timer info with the new phase
                                     {
-                                        let mut info = timer_info.lock().unwrap();
-                                        was_paused = info.state == TimerState::Paused;
-                                        info.current_phase = Some(next_phase.clone());
-                                        info.time_remaining = Some(Duration::minutes(next_phase.duration as i64));
-                                        info.elapsed_time = Duration::zero();
+                                        let mut info = write_or_recover(&timer_info);
+                                        was_paused = matches!(info.state, TimerState::Paused | TimerState::WaitingForStart);
+                                        begin_phase(&mut info, &next_phase);
                                         
                                         if was_paused {
                                             info.state = TimerState::Running;
@@ -448,14 +972,15 @@ async fn timer_logic_task(
                                     // Send event after releasing the lock
                                     let send_result = event_tx.send(TimerEvent::PhaseChanged {
                                         phase: next_phase,
+                                        silent,
                                     }).await;
                                     if send_result.is_err() {
-                                        println!("Failed to send phase changed event");
+                                        warn!("Failed to send phase changed event");
                                     }
                                 } else {
                                     // End of workflow
                                     {
-                                        let mut info = timer_info.lock().unwrap();
+                                        let mut info = write_or_recover(&timer_info);
                                         info.state = TimerState::Completed;
                                         info.current_phase = None;
                                         info.time_remaining = None;
@@ -467,12 +992,87 @@ async fn timer_logic_task(
                                     // Send event after releasing the lock
                                     let send_result = event_tx.send(TimerEvent::Completed).await;
                                     if send_result.is_err() {
-                                        println!("Failed to send completed event");
+                                        warn!("Failed to send completed event");
                                     }
                                 }
                             }
                         }
                     }
+                    
+                    TimerCommand::Previous { keep_progress, silent } => {
+                        let (workflow_opt, phase_opt, is_running_or_paused) = {
+                            let info = read_or_recover(&timer_info);
+                            (
+                                info.current_workflow.clone(),
+                                info.current_phase.clone(),
+                                matches!(info.state, TimerState::Running | TimerState::Paused | TimerState::WaitingForStart)
+                            )
+                        };
+                        
+                        if !is_running_or_paused {
+                            continue;
+                        }
+                        
+                        // Going back leaves a mandatory break just like skipping it would
+                        if phase_opt.as_ref().is_some_and(|phase| !phase.skippable) {
+                            warn!("Phase can't be skipped, ignoring previous");
+                            continue;
+                        }
+                        
+                        let (Some(workflow), Some(current_phase)) = (workflow_opt, phase_opt) else {
+                            continue;
+                        };
+                        let Some(current_index) = workflow.phases.iter().position(|p| p.name == current_phase.name) else {
+                            continue;
+                        };
+                        
+                        // Wrap around a repeatable workflow; otherwise the first phase restarts itself
+                        let previous_index = match current_index {
+                            0 if workflow.repeatable => workflow.phases.len() - 1,
+                            0 => 0,
+                            index => index - 1,
+                        };
+                        let previous_phase = workflow.phases[previous_index].clone();
+                        
+                        {
+                            let mut info = write_or_recover(&timer_info);
+                            
+                            // The current work phase no longer counts, and a work phase
+                            // we go back to was counted when it first began
+                            if !current_phase.is_break() {
+                                info.cycle_count = info.cycle_count.saturating_sub(1);
+                            }
+                            begin_phase(&mut info, &previous_phase);
+                            if !previous_phase.is_break() && previous_index != current_index {
+                                info.cycle_count = info.cycle_count.saturating_sub(1);
+                            }
+                            
+                            // How far the phase got isn't recorded, so resume a fixed time before its end
+                            if keep_progress && previous_phase.reps.is_none() {
+                                let total = Duration::minutes(previous_phase.duration as i64);
+                                let remaining = Duration::minutes(config::get().keep_progress_minutes as i64).min(total);
+                                info.time_remaining = Some(remaining);
+                                info.elapsed_time = total - remaining;
+                            }
+                            
+                            if matches!(info.state, TimerState::Paused | TimerState::WaitingForStart) {
+                                info.state = TimerState::Running;
+                                info.pause_time = None;
+                            }
+                            
+                            // Save state after going back
+                            save_timer_state(&info);
+                        }
+                        
+                        // Send event after releasing the lock
+                        let send_result = event_tx.send(TimerEvent::PhaseChanged {
+                            phase: previous_phase,
+                            silent,
+                        }).await;
+                        if send_result.is_err() {
+                            warn!("Failed to send phase changed event");
+                        }
+                    }
                 }
             }
         }
@@ -480,44 +1080,602 @@ async fn timer_logic_task(
 }
 
 // A new task to consume events from the channel
-async fn event_consumer_task(mut event_rx: mpsc::Receiver<TimerEvent>) {
+async fn event_consumer_task(
+    timer_info: Arc<RwLock<TimerInfo>>,
+    mut event_rx: mpsc::Receiver<TimerEvent>,
+    command_tx: mpsc::Sender<TimerCommand>,
+    events: broadcast::Sender<TimerEvent>,
+) {
+    let notification_service = NotificationService::new();
+    
     while let Some(event) = event_rx.recv().await {
-        match event {
-            TimerEvent::Started { .. } => {
-                // Handle start event
+        let info = read_or_recover(&timer_info).clone();
+        
+        // Nobody subscribing is fine, the event is only dropped
+        let _ = events.send(event.clone());
+        
+        let (hook_event, notification) = match event {
+            TimerEvent::Started { .. } => (Some(HookEvent::Started), Ok(())),
+            TimerEvent::PhaseChanged { phase, silent } => {
+                if !silent {
+                    tts::announce_phase(&phase);
+                }
+                
+                let notification = match &info.current_status {
+                    _ if silent => Ok(()),
+                    // Notification buttons talk straight to this timer
+                    Some(status) if phase.is_break() => {
+                        notification_service.send_break_notification(&phase, status, command_tx.clone())
+                    },
+                    Some(status) => notification_service.send_phase_change_notification(&phase, status),
+                    None => Ok(()),
+                };
+                (Some(HookEvent::PhaseChanged), notification)
             },
-            TimerEvent::PhaseChanged { .. } => {
-                // Handle phase change event
+            TimerEvent::Paused => (Some(HookEvent::Paused), Ok(())),
+            TimerEvent::Resumed => (Some(HookEvent::Resumed), Ok(())),
+            TimerEvent::Stopped => (Some(HookEvent::Stopped), Ok(())),
+            TimerEvent::Completed => {
+                let notification = match &info.current_workflow {
+                    Some(workflow) => notification_service.send_timer_completed_notification(workflow),
+                    None => Ok(()),
+                };
+                (Some(HookEvent::Completed), notification)
             },
-            TimerEvent::Paused => {
-                // Handle pause event
+            TimerEvent::DayStarted => {
+                (Some(HookEvent::DayStarted), notification_service.send_day_start_notification())
             },
-            TimerEvent::Resumed => {
-                // Handle resume event
+            TimerEvent::WeekStarted => {
+                (None, notification_service.send_weekly_report_notification())
             },
-            TimerEvent::Stopped => {
-                // Handle stop event
+            TimerEvent::BreakReminder { worked } => {
+                (None, notification_service.send_break_reminder_notification(worked))
             },
-            TimerEvent::Completed => {
-                // Handle completion event
+            TimerEvent::PhaseEnding { phase, remaining } => {
+                (None, notification_service.send_phase_ending_notification(&phase, remaining))
             },
+        };
+        
+        if let Err(e) = notification {
+            warn!("{}", e);
+        }
+        
+        if let Some(hook_event) = hook_event {
+            hooks::run_hook(hook_event, &info);
         }
     }
 }
 
-// Helper function to save timer state to persistence
-fn save_timer_state(info: &TimerInfo) {
-    let persistent_state = persistence::PersistentState {
-        timer_state: info.state.clone(),
-        current_phase: info.current_phase.clone(),
-        current_status: info.current_status.clone(),
-        current_workflow: info.current_workflow.clone(),
-        start_time: info.start_time,
-        elapsed_seconds: info.elapsed_time.num_seconds() as u64,
-        last_saved: Local::now(),
+// Make `phase` the current one with its full duration ahead of it
+fn begin_phase(info: &mut TimerInfo, phase: &Phase) {
+    info.current_phase = Some(phase.clone());
+    info.elapsed_time = Duration::zero();
+    info.phase_end_warned = false;
+    info.paused_time = Duration::zero();
+    
+    // Rep-based phases have no clock to run out, only reps to count down
+    info.reps_remaining = phase.reps;
+    info.time_remaining = match phase.reps {
+        Some(_) => None,
+        None => Some(Duration::minutes(phase.duration as i64)),
     };
     
-    if let Err(e) = persistence::update(persistent_state) {
+    if phase.is_break() {
+        info.continuous_work = Duration::zero();
+    } else {
+        info.cycle_count += 1;
+    }
+}
+
+// Add the current phase to today's count if it's a work phase, starting over on a new day
+fn count_completed(info: &mut TimerInfo) {
+    if info.current_phase.as_ref().is_none_or(|phase| phase.is_break()) {
+        return;
+    }
+    
+    let today = clock::now().date_naive();
+    info.completed_count = info.today_count() + 1;
+    info.completed_date = Some(today);
+}
+
+// The phase after `current` finishes: the next one, or the first again in a repeatable workflow
+fn phase_after(workflow: &Workflow, current: &Phase) -> Option<Phase> {
+    let index = workflow.phases.iter().position(|p| p.name == current.name)?;
+    
+    match workflow.phases.get(index + 1) {
+        Some(next) => Some(next.clone()),
+        None if workflow.repeatable => workflow.phases.first().cloned(),
+        None => None,
+    }
+}
+
+// Shift the wall-clock timestamps by a backward clock step so "started at" and
+// history times stay consistent with the time that actually passed
+fn reanchor_timestamps(info: &mut TimerInfo, step_back: Duration) {
+    info.start_time = info.start_time.map(|time| time - step_back);
+    info.pause_time = info.pause_time.map(|time| time - step_back);
+}
+
+// Swap the freshly begun first phase for the one `stop` interrupted, with its progress,
+// when that was in the same workflow and the phase still exists
+fn continue_stopped_session(info: &mut TimerInfo, workflow: &Workflow) {
+    let Some(stopped) = info.last_stopped.take().filter(|s| s.workflow == workflow.name) else {
+        return;
+    };
+    let Some(phase) = workflow.phases.iter().find(|p| p.name == stopped.phase) else {
+        return;
+    };
+    
+    begin_phase(info, phase);
+    info.cycle_count = stopped.cycle_count;
+    info.elapsed_time = stopped.elapsed;
+    
+    match phase.reps {
+        Some(_) => info.reps_remaining = stopped.reps_remaining.or(phase.reps),
+        None => {
+            let total = Duration::minutes(phase.duration as i64);
+            info.time_remaining = Some((total - stopped.elapsed).max(Duration::seconds(1)));
+        }
+    }
+}
+
+// After `finished` ends, wait for an explicit start when it doesn't auto-advance
+fn hold_if_manual_advance(info: &mut TimerInfo, finished: &Phase) {
+    if !finished.auto_start_next {
+        info.state = TimerState::WaitingForStart;
+    }
+}
+
+// Helper function to save timer state to persistence
+fn save_timer_state(info: &TimerInfo) {
+    if let Err(e) = persistence::update(persistence::PersistentState::from(info)) {
         eprintln!("Failed to save timer state: {}", e);
     }
-} 
\ No newline at end of file
+}
+
+impl From<&TimerInfo> for persistence::PersistentState {
+    fn from(info: &TimerInfo) -> Self {
+        Self {
+            timer_state: info.state.clone(),
+            current_phase: info.current_phase.clone(),
+            current_status: info.current_status.clone(),
+            current_workflow: info.current_workflow.as_deref().cloned(),
+            start_time: info.start_time,
+            elapsed_seconds: info.elapsed_time.num_seconds() as u64,
+            pause_time: info.pause_time,
+            cycle_count: info.cycle_count,
+            session_elapsed_seconds: info.session_elapsed.num_seconds() as u64,
+            continuous_work_seconds: info.continuous_work.num_seconds() as u64,
+            last_start_date: info.last_start_date,
+            reps_remaining: info.reps_remaining,
+            last_stopped: info.last_stopped.clone(),
+            phase_end_warned: info.phase_end_warned,
+            paused_seconds: info.paused_time.num_seconds() as u64,
+            completed_count: info.completed_count,
+            completed_date: info.completed_date,
+            last_saved: clock::now(),
+        }
+    }
+}
+
+// Remember where the session left off for `start --continue`
+fn remember_stopped_session(info: &mut TimerInfo) {
+    if let (Some(workflow), Some(phase)) = (&info.current_workflow, &info.current_phase) {
+        info.last_stopped = Some(StoppedSession {
+            workflow: workflow.name.clone(),
+            phase: phase.name.clone(),
+            elapsed: info.elapsed_time,
+            reps_remaining: info.reps_remaining,
+            cycle_count: info.cycle_count,
+        });
+    }
+}
+
+// Leave the session, as `stop` does
+fn reset_to_idle(info: &mut TimerInfo) {
+    info.state = TimerState::Idle;
+    info.current_phase = None;
+    info.time_remaining = None;
+    info.reps_remaining = None;
+    info.start_time = None;
+    info.pause_time = None;
+    info.cycle_count = 0;
+    info.session_elapsed = Duration::zero();
+    info.continuous_work = Duration::zero();
+}
+
+// The remaining time isn't persisted; `Timer::new` derives it from the
+// phase and elapsed time after loading
+impl From<persistence::PersistentState> for TimerInfo {
+    fn from(state: persistence::PersistentState) -> Self {
+        Self {
+            state: state.timer_state,
+            current_phase: state.current_phase,
+            time_remaining: None,
+            elapsed_time: Duration::seconds(state.elapsed_seconds as i64),
+            current_status: state.current_status,
+            current_workflow: state.current_workflow.map(Arc::new),
+            start_time: state.start_time,
+            pause_time: state.pause_time,
+            cycle_count: state.cycle_count,
+            session_elapsed: Duration::seconds(state.session_elapsed_seconds as i64),
+            continuous_work: Duration::seconds(state.continuous_work_seconds as i64),
+            last_start_date: state.last_start_date,
+            reps_remaining: state.reps_remaining,
+            last_stopped: state.last_stopped,
+            phase_end_warned: state.phase_end_warned,
+            paused_time: Duration::seconds(state.paused_seconds as i64),
+            completed_count: state.completed_count,
+            completed_date: state.completed_date,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    use crate::test_support;
+    
+    // A non-repeatable workflow of timed phases, `(name, minutes)` each
+    fn workflow(phases: &[(&str, u32)]) -> Workflow {
+        let phases = phases.iter().map(|(name, minutes)| Phase::new(name, *minutes)).collect();
+        Workflow::new("Test").with_phases(phases).with_repeatable(false)
+    }
+    
+    // A timer on the sandbox state. Time is moved half a second off the tick boundaries,
+    // so `run_for` never lands on the same instant as a tick
+    async fn new_timer() -> Timer {
+        let timer = Timer::new(ResumeMode::Resume).await;
+        time::sleep(time::Duration::from_millis(500)).await;
+        timer
+    }
+    
+    async fn send(timer: &Timer, command: TimerCommand) {
+        timer.send_command(command).await.expect("send command");
+        settle().await;
+    }
+    
+    async fn start(timer: &Timer, workflow: Workflow) {
+        send(timer, TimerCommand::Start { workflow: Some(workflow), status: Some(Status::default()), continue_session: false }).await;
+    }
+    
+    // Let the timer handle what it was sent without moving the clock
+    async fn settle() {
+        for _ in 0..20 {
+            tokio::task::yield_now().await;
+        }
+    }
+    
+    // Let `seconds` ticks go by on the paused clock
+    async fn run_for(seconds: u64) {
+        time::sleep(time::Duration::from_secs(seconds)).await;
+        settle().await;
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn continuing_a_stopped_session_keeps_its_elapsed_time() {
+        let _sandbox = test_support::sandbox().await;
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        run_for(90).await;
+        send(&timer, TimerCommand::Stop { save_session: false }).await;
+        
+        send(&timer, TimerCommand::Start {
+            workflow: Some(workflow(&[("Work", 25), ("Break", 5)])),
+            status: Some(Status::default()),
+            continue_session: true,
+        }).await;
+        
+        let info = timer.get_info();
+        assert_eq!(info.state, TimerState::Running);
+        assert_eq!(info.current_phase.map(|phase| phase.name).as_deref(), Some("Work"));
+        assert_eq!(info.elapsed_time, Duration::seconds(90));
+        assert_eq!(info.time_remaining, Some(Duration::seconds(25 * 60 - 90)));
+    }
+    
+    #[test]
+    fn timer_state_displays_readable_labels() {
+        assert_eq!(TimerState::Running.to_string(), "Running");
+        assert_eq!(TimerState::WaitingForStart.to_string(), "Waiting for start");
+    }
+    
+    #[test]
+    fn porcelain_state_names_are_single_words() {
+        assert_eq!(TimerState::Running.porcelain_name(), "Running");
+        assert_eq!(TimerState::WaitingForStart.porcelain_name(), "WaitingForStart");
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn session_elapsed_keeps_counting_across_phase_changes() {
+        let _sandbox = test_support::sandbox().await;
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 1), ("Break", 1), ("Work again", 1)])).await;
+        run_for(150).await;
+        
+        let info = timer.get_info();
+        assert_eq!(info.current_phase.map(|phase| phase.name).as_deref(), Some("Work again"));
+        assert_eq!(info.elapsed_time, Duration::seconds(30));
+        assert_eq!(info.session_elapsed, Duration::seconds(150));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn skipping_a_non_skippable_phase_changes_nothing() {
+        let _sandbox = test_support::sandbox().await;
+        let mut workflow = workflow(&[("Work", 25), ("Break", 5)]);
+        workflow.phases[0].skippable = false;
+        let timer = new_timer().await;
+        start(&timer, workflow).await;
+        run_for(30).await;
+        let before = timer.get_info();
+        
+        send(&timer, TimerCommand::Skip { silent: false }).await;
+        
+        let after = timer.get_info();
+        assert_eq!(after.state, TimerState::Running);
+        assert_eq!(after.current_phase, before.current_phase);
+        assert_eq!(after.elapsed_time, before.elapsed_time);
+        assert_eq!(after.time_remaining, before.time_remaining);
+    }
+    
+    #[test]
+    fn timer_info_survives_a_save_and_load() {
+        let workflow = workflow(&[("Work", 25), ("Break", 5)]);
+        let started = Local.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let info = TimerInfo {
+            state: TimerState::Paused,
+            current_phase: Some(workflow.phases[1].clone()),
+            elapsed_time: Duration::seconds(130),
+            current_status: Some(Status::default()),
+            current_workflow: Some(Arc::new(workflow)),
+            start_time: Some(started),
+            pause_time: Some(started + Duration::minutes(27)),
+            cycle_count: 2,
+            session_elapsed: Duration::seconds(1630),
+            continuous_work: Duration::seconds(300),
+            last_start_date: Some(started.date_naive()),
+            phase_end_warned: true,
+            paused_time: Duration::seconds(45),
+            completed_count: 3,
+            completed_date: Some(started.date_naive()),
+            ..TimerInfo::default()
+        };
+        
+        let saved = serde_json::to_string(&persistence::PersistentState::from(&info)).unwrap();
+        let loaded = TimerInfo::from(serde_json::from_str::<persistence::PersistentState>(&saved).unwrap());
+        
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&info).unwrap());
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn restarting_while_paused_stays_paused_with_the_same_time_left() {
+        let _sandbox = test_support::sandbox().await;
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        run_for(60).await;
+        send(&timer, TimerCommand::Pause).await;
+        let before = timer.get_info();
+        
+        let restarted = Timer::new(ResumeMode::Resume).await;
+        
+        let after = restarted.get_info();
+        assert_eq!(after.state, TimerState::Paused);
+        assert_eq!(after.pause_time, before.pause_time);
+        assert_eq!(after.time_remaining, Some(Duration::minutes(24)));
+        assert_eq!(after.time_remaining, before.time_remaining);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn changing_status_mid_phase_keeps_the_time_left() {
+        let _sandbox = test_support::sandbox().await;
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        run_for(90).await;
+        
+        send(&timer, TimerCommand::SetStatus { status: Status::new("reading") }).await;
+        
+        let info = timer.get_info();
+        assert_eq!(info.current_status.map(|status| status.name).as_deref(), Some("reading"));
+        assert_eq!(info.state, TimerState::Running);
+        assert_eq!(info.current_phase.map(|phase| phase.name).as_deref(), Some("Work"));
+        assert_eq!(info.time_remaining, Some(Duration::seconds(25 * 60 - 90)));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn starting_an_empty_workflow_is_refused() {
+        let _sandbox = test_support::sandbox().await;
+        let timer = new_timer().await;
+        
+        let result = timer.send_command(TimerCommand::Start {
+            workflow: Some(Workflow::new("Empty")),
+            status: None,
+            continue_session: false,
+        }).await;
+        settle().await;
+        
+        assert_eq!(result, Err("Workflow has no phases"));
+        assert_eq!(timer.get_info().state, TimerState::Idle);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn a_backward_clock_jump_moves_the_start_time_and_keeps_counting() {
+        let _sandbox = test_support::sandbox().await;
+        let started = Local.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        test_support::set_clock(started);
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        run_for(60).await;
+        
+        test_support::advance_clock(-Duration::hours(1));
+        run_for(1).await;
+        
+        let info = timer.get_info();
+        assert_eq!(info.start_time, Some(started - Duration::hours(1)));
+        assert_eq!(info.state, TimerState::Running);
+        assert_eq!(info.elapsed_time, Duration::seconds(61));
+        assert_eq!(info.time_remaining, Some(Duration::seconds(25 * 60 - 61)));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn the_phase_ending_warning_fires_once_per_phase() {
+        let _sandbox = test_support::sandbox().await;
+        config::set(config::Config {
+            notification_enabled: false,
+            warn_before_seconds: Some(30),
+            ..config::Config::default()
+        });
+        let timer = new_timer().await;
+        let mut events = timer.subscribe();
+        start(&timer, workflow(&[("Work", 1), ("Break", 1)])).await;
+        run_for(59).await;
+        
+        let mut warnings = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let TimerEvent::PhaseEnding { phase, remaining } = event {
+                warnings.push((phase.name, remaining));
+            }
+        }
+        assert_eq!(warnings, [("Work".to_string(), Duration::seconds(30))]);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn paused_time_adds_up_across_pauses() {
+        let _sandbox = test_support::sandbox().await;
+        test_support::set_clock(Local.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap());
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        
+        for pause_minutes in [2, 3] {
+            run_for(30).await;
+            send(&timer, TimerCommand::Pause).await;
+            test_support::advance_clock(Duration::minutes(pause_minutes));
+            send(&timer, TimerCommand::Resume).await;
+        }
+        send(&timer, TimerCommand::Pause).await;
+        test_support::advance_clock(Duration::minutes(1));
+        
+        let info = timer.get_info();
+        assert_eq!(info.paused_time, Duration::minutes(5));
+        assert_eq!(info.phase_paused(), Duration::minutes(6));
+        assert_eq!(info.elapsed_time, Duration::seconds(60));
+        
+        let entry = HistoryEntry::from_timer(&info, true).unwrap();
+        assert_eq!((entry.duration_seconds, entry.paused_seconds), (60, 6 * 60));
+    }
+    
+    // Leave a session running 60s into its first phase in the saved state, then start a
+    // fresh timer on it the way the daemon would with `resume_on_startup = on_startup`
+    async fn restart_mid_session(on_startup: ResumeMode) -> TimerInfo {
+        let timer = new_timer().await;
+        start(&timer, workflow(&[("Work", 25), ("Break", 5)])).await;
+        run_for(60).await;
+        timer.save_state();
+        
+        Timer::new(on_startup).await.get_info()
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn resume_on_startup_resume_keeps_the_session_running() {
+        let _sandbox = test_support::sandbox().await;
+        let info = restart_mid_session(ResumeMode::Resume).await;
+        
+        assert_eq!(info.state, TimerState::Running);
+        assert_eq!(info.time_remaining, Some(Duration::minutes(24)));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn resume_on_startup_pause_restores_the_session_paused() {
+        let _sandbox = test_support::sandbox().await;
+        let info = restart_mid_session(ResumeMode::Pause).await;
+        
+        assert_eq!(info.state, TimerState::Paused);
+        assert!(info.pause_time.is_some());
+        assert_eq!(info.time_remaining, Some(Duration::minutes(24)));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn resume_on_startup_idle_stops_the_session_for_start_continue() {
+        let _sandbox = test_support::sandbox().await;
+        let info = restart_mid_session(ResumeMode::Idle).await;
+        
+        assert_eq!(info.state, TimerState::Idle);
+        assert_eq!(info.current_phase, None);
+        let stopped = info.last_stopped.expect("stopped session to continue");
+        assert_eq!((stopped.phase.as_str(), stopped.elapsed), ("Work", Duration::seconds(60)));
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn the_watchdog_resets_a_running_timer_without_a_phase() {
+        let _sandbox = test_support::sandbox().await;
+        persistence::update(persistence::PersistentState {
+            timer_state: TimerState::Running,
+            current_phase: None,
+            ..persistence::PersistentState::default()
+        }).unwrap();
+        let timer = new_timer().await;
+        
+        run_for(2).await;
+        assert_eq!(timer.get_info().state, TimerState::Running);
+        
+        run_for(2).await;
+        assert_eq!(timer.get_info().state, TimerState::Idle);
+        assert_eq!(persistence::get().timer_state, TimerState::Idle);
+    }
+    
+    // Latency of `get_info` with four reader threads and a status change every
+    // millisecond taking the write lock. A measurement rather than a check, so it only
+    // runs on request: `cargo test --release get_info_latency -- --ignored --nocapture`
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    #[ignore]
+    async fn get_info_latency_under_concurrent_reads_and_writes() {
+        use std::time::{Duration as StdDuration, Instant};
+        
+        let _sandbox = test_support::sandbox().await;
+        let timer = Arc::new(Timer::new(ResumeMode::Resume).await);
+        timer.send_command(TimerCommand::Start { workflow: None, status: None, continue_session: false }).await.unwrap();
+        let deadline = Instant::now() + StdDuration::from_secs(5);
+        
+        let writer = {
+            let timer = Arc::clone(&timer);
+            tokio::spawn(async move {
+                let mut writes = 0;
+                while Instant::now() < deadline {
+                    timer.send_command(TimerCommand::SetStatus { status: Status::default() }).await.unwrap();
+                    writes += 1;
+                    time::sleep(time::Duration::from_millis(1)).await;
+                }
+                writes
+            })
+        };
+        
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let timer = Arc::clone(&timer);
+                std::thread::spawn(move || {
+                    let mut samples = Vec::new();
+                    while Instant::now() < deadline {
+                        let start = Instant::now();
+                        std::hint::black_box(timer.get_info());
+                        samples.push(start.elapsed());
+                    }
+                    samples
+                })
+            })
+            .collect();
+        
+        let mut samples: Vec<StdDuration> = readers.into_iter().flat_map(|reader| reader.join().unwrap()).collect();
+        let writes = writer.await.unwrap();
+        samples.sort();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        println!(
+            "{} reads, {} writes: p50 {:?}, p99 {:?}, p99.9 {:?}, max {:?}",
+            samples.len(),
+            writes,
+            percentile(50),
+            percentile(99),
+            samples[(samples.len() - 1) * 999 / 1000],
+            samples[samples.len() - 1]
+        );
+    }
+}
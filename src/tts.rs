@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "notifications")]
+use log::warn;
+#[cfg(feature = "notifications")]
+use tokio::process::Command;
+
+use crate::config;
+use crate::workflow::Phase;
+
+// Set by `--announce` for this run only, on top of `tts_enabled`
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+/// Speak phase changes for the rest of this run, whatever `tts_enabled` says
+pub fn enable() {
+    FORCED.store(true, Ordering::Relaxed);
+}
+
+/// Speak the phase that just began through `tts_command`, e.g. "Break, 5 minutes"
+pub fn announce_phase(phase: &Phase) {
+    let config = config::get();
+    if !config.tts_enabled && !FORCED.load(Ordering::Relaxed) {
+        return;
+    }
+    
+    let (amount, unit) = match phase.reps {
+        Some(reps) => (reps, "rep"),
+        None => (phase.duration, "minute"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    
+    speak(&config.tts_command, &format!("{}, {} {}{}", phase.name, amount, unit, plural));
+}
+
+// Like sounds, speech runs detached and tokio reaps it
+#[cfg(feature = "notifications")]
+fn speak(command: &str, text: &str) {
+    if let Err(e) = Command::new(command).arg(text).spawn() {
+        warn!("Failed to announce '{}' with {}: {}", text, command, e);
+    }
+}
+
+// Built without the `notifications` feature: stay quiet
+#[cfg(not(feature = "notifications"))]
+fn speak(_command: &str, _text: &str) {}
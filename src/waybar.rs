@@ -2,17 +2,26 @@ use chrono::Duration;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use unicode_width::UnicodeWidthStr;
 
+use crate::clock;
 use crate::config;
+use crate::history;
+use crate::lock::lock_or_recover;
+use crate::schedule;
+use crate::status::StatusManager;
 use crate::timer::{TimerInfo, TimerState};
+use crate::workflow::Phase;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WaybarOutput {
     pub text: String,
     pub tooltip: Option<String>,
-    pub class: Option<String>,
+    // Waybar accepts either a single class or a list of them
+    pub class: Vec<String>,
     pub percentage: Option<u8>,
     #[serde(rename = "alt")]
     pub alt_text: Option<String>,
@@ -23,7 +32,7 @@ impl Default for WaybarOutput {
         Self {
             text: "🍅".to_string(),
             tooltip: None,
-            class: None,
+            class: Vec::new(),
             percentage: None,
             alt_text: None,
         }
@@ -32,141 +41,506 @@ impl Default for WaybarOutput {
 
 lazy_static::lazy_static! {
     static ref WAYBAR_OUTPUT: Arc<Mutex<WaybarOutput>> = Arc::new(Mutex::new(WaybarOutput::default()));
+    static ref MINI_OUTPUT: Arc<Mutex<WaybarOutput>> = Arc::new(Mutex::new(WaybarOutput::default()));
 }
 
+/// The configured Unix socket for Waybar; always `None` on platforms without Unix sockets
 #[allow(dead_code)]
 pub fn get_waybar_socket_path() -> Option<PathBuf> {
+    if !cfg!(unix) {
+        return None;
+    }
+    
     let config = config::get();
     
     config.waybar_integration.socket_path.map(PathBuf::from)
 }
 
+/// `$XDG_RUNTIME_DIR/tomato-clock/waybar-output.json`, unless the file older versions
+/// wrote to the config directory is still there for an existing Waybar config to read
 pub fn get_waybar_output_path() -> PathBuf {
-    let mut path = config::get_config_dir();
+    let legacy_path = config::get_config_dir().join("waybar-output.json");
+    if legacy_path.exists() {
+        return legacy_path;
+    }
+    
+    let mut path = config::get_runtime_dir();
     path.push("waybar-output.json");
     path
 }
 
+/// Placeholders `format` and `mini_format` understand
+pub const FORMAT_PLACEHOLDERS: &[&str] = &[
+    "icon", "status", "phase", "remaining", "cycle", "progress_bar", "heartbeat", "reps",
+    "workflow",
+];
+
+/// Runtime override of `format` written by `set-format`, read again whenever it changes
+pub fn get_format_override_path() -> PathBuf {
+    let mut path = config::get_state_dir();
+    path.push("waybar-format");
+    path
+}
+
+// The override file's size and modification time with the format read from it;
+// the file is only read again once either changes
+type FormatOverrideKey = (u64, Option<SystemTime>);
+
+static FORMAT_OVERRIDE: Mutex<Option<(FormatOverrideKey, Option<String>)>> = Mutex::new(None);
+
+// `set-format`'s override if there is one, otherwise the configured format
+fn active_format(configured: &str) -> String {
+    format_override().unwrap_or_else(|| configured.to_string())
+}
+
+fn format_override() -> Option<String> {
+    let path = get_format_override_path();
+    let metadata = fs::metadata(&path).ok();
+    let key = (
+        metadata.as_ref().map_or(0, |m| m.len()),
+        metadata.and_then(|m| m.modified().ok()),
+    );
+    
+    let mut cache = lock_or_recover(&FORMAT_OVERRIDE);
+    if let Some((cached_key, format)) = &*cache {
+        if *cached_key == key {
+            return format.clone();
+        }
+    }
+    
+    let format = fs::read_to_string(&path)
+        .ok()
+        .filter(|format| !format.trim().is_empty())
+        .map(|format| format.trim_end_matches('\n').to_string());
+    *cache = Some((key, format.clone()));
+    format
+}
+
+/// Reject a format using a `{placeholder}` we don't fill in, which would show up verbatim
+pub fn validate_format(format: &str) -> Result<(), String> {
+    let mut rest = format;
+    
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err("Unclosed '{' in format".to_string());
+        };
+        
+        let name = &rest[start + 1..start + end];
+        if !FORMAT_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder '{{{}}}', expected one of: {}",
+                name,
+                FORMAT_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(" ")
+            ));
+        }
+        
+        rest = &rest[start + end + 1..];
+    }
+    
+    Ok(())
+}
+
+/// Where the compact second output goes, if `mini_output_path` is set
+pub fn get_mini_output_path() -> Option<PathBuf> {
+    let path = config::get().waybar_integration.mini_output_path?;
+    
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+/// `MM:SS`; negative durations (a phase that overshot its end) show as `00:00`
 pub fn format_time_remaining(duration: Duration) -> String {
-    let total_seconds = duration.num_seconds();
+    let total_seconds = duration.num_seconds().max(0);
     let minutes = total_seconds / 60;
     let seconds = total_seconds % 60;
     
     format!("{:02}:{:02}", minutes, seconds)
 }
 
-pub fn update_waybar_output(timer_info: &TimerInfo) -> Result<(), String> {
+// Time left in `phase`, falling back to its duration minus the elapsed time
+fn remaining_in_phase(timer_info: &TimerInfo, phase: &Phase) -> Duration {
+    if let Some(time_remaining) = timer_info.time_remaining {
+        return time_remaining;
+    }
+    
+    let total_duration = Duration::minutes(phase.duration as i64);
+    if total_duration > timer_info.elapsed_time {
+        total_duration - timer_info.elapsed_time
+    } else {
+        Duration::zero()
+    }
+}
+
+/// Percent of `phase` done: reps counted for rep-based phases, time elapsed otherwise
+pub fn current_phase_percentage(timer_info: &TimerInfo, phase: &Phase) -> Option<u8> {
+    if let (Some(reps), Some(reps_left)) = (phase.reps, timer_info.reps_remaining) {
+        let done = reps.saturating_sub(reps_left);
+        return phase_percentage(Duration::seconds(done as i64), Duration::seconds(reps as i64));
+    }
+    
+    let total = Duration::minutes(phase.duration as i64);
+    phase_percentage(total - remaining_in_phase(timer_info, phase), total)
+}
+
+/// `Nm` (whole minutes) above a minute and `MM:SS` within the last one, for
+/// `compact_minutes`: 61s shows as `1m`, 59s as `00:59`
+pub fn format_time_compact(duration: Duration) -> String {
+    let total_seconds = duration.num_seconds().max(0);
+    if total_seconds > 60 {
+        format!("{}m", total_seconds / 60)
+    } else {
+        format_time_remaining(duration)
+    }
+}
+
+// Rep-based phases count up from their start, timed ones down to their end
+fn phase_clock(timer_info: &TimerInfo, phase: &Phase, remaining: Duration, compact: bool) -> String {
+    let clock = match phase.reps {
+        Some(_) => timer_info.elapsed_time,
+        None => remaining,
+    };
+    
+    if compact {
+        format_time_compact(clock)
+    } else {
+        format_time_remaining(clock)
+    }
+}
+
+// `{workflow}`: the active workflow's name, empty without one
+fn workflow_name(timer_info: &TimerInfo) -> &str {
+    timer_info.current_workflow.as_ref().map(|w| w.name.as_str()).unwrap_or("")
+}
+
+// `{reps}`: reps left in a rep-based phase, empty otherwise
+fn reps_left(timer_info: &TimerInfo) -> String {
+    timer_info.reps_remaining.map(|reps| reps.to_string()).unwrap_or_default()
+}
+
+// Share of `total` that has elapsed, clamped to 0-100 so overtime shows as full.
+// `None` for a zero-length phase, where there's nothing to divide by
+pub fn phase_percentage(elapsed: Duration, total: Duration) -> Option<u8> {
+    let total_seconds = total.num_seconds();
+    if total_seconds <= 0 {
+        return None;
+    }
+    
+    let elapsed_seconds = elapsed.num_seconds().clamp(0, total_seconds);
+    let percent = elapsed_seconds.saturating_mul(100) / total_seconds;
+    
+    Some(percent.clamp(0, 100) as u8)
+}
+
+// Parse `#rrggbb` into its components
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// The color `percentage` of the way from `start` to `end`, or `None` if either isn't `#rrggbb`
+fn gradient_color(start: &str, end: &str, percentage: u8) -> Option<String> {
+    let (start, end) = (parse_hex_color(start)?, parse_hex_color(end)?);
+    let t = percentage.min(100) as i32;
+    
+    let mix = |i: usize| (start[i] as i32 + (end[i] as i32 - start[i] as i32) * t / 100) as u8;
+    Some(format!("#{:02x}{:02x}{:02x}", mix(0), mix(1), mix(2)))
+}
+
+// A `width`-character bar like `████░░░░░░`, filled by `percentage`
+fn render_progress_bar(percentage: Option<u8>, width: usize) -> String {
+    let filled = percentage.unwrap_or(0) as usize * width / 100;
+    
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+// Pad `text` with trailing spaces to `min_width` terminal columns. Emoji and CJK
+// characters are several bytes but two columns wide, so count display width
+fn pad_to_width(text: &str, min_width: usize) -> String {
+    let width = UnicodeWidthStr::width(text);
+    if width >= min_width {
+        return text.to_string();
+    }
+    
+    format!("{}{}", text, " ".repeat(min_width - width))
+}
+
+// Text wrapped in Pango markup must not contain raw markup characters
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Fill in `tooltip_format`; an empty template means no tooltip at all
+fn render_tooltip(template: &str, timer_info: &TimerInfo, phase: &Phase, status_name: &str, remaining: Duration) -> Option<String> {
+    if template.is_empty() {
+        return None;
+    }
+    
+    // A paused phase has no end time yet, and one computed now would drift while the
+    // paused output stays as written
+    let ends_at = if timer_info.state == TimerState::Paused {
+        "—".to_string()
+    } else {
+        (clock::now() + remaining).format("%H:%M").to_string()
+    };
+    
+    // TOML literal strings keep `\n` as two characters, so accept that too
+    let tooltip = template
+        .replace("\\n", "\n")
+        .replace("{status}", status_name)
+        .replace("{phase}", &phase.name)
+        .replace("{remaining}", &format_time_remaining(remaining))
+        .replace("{elapsed}", &format_time_remaining(timer_info.elapsed_time))
+        .replace("{session}", &format_time_remaining(timer_info.session_elapsed))
+        .replace("{reps}", &reps_left(timer_info))
+        .replace("{workflow}", workflow_name(timer_info))
+        .replace("{description}", phase.description.as_deref().unwrap_or(""))
+        .replace("{ends_at}", &ends_at)
+        .replace("{today_count}", &timer_info.today_count().to_string());
+    
+    // Counting the streak reads the history, so only do it when it's shown
+    let tooltip = if tooltip.contains("{streak}") {
+        tooltip.replace("{streak}", &history::streak().to_string())
+    } else {
+        tooltip
+    };
+    
+    Some(tooltip)
+}
+
+pub fn update_waybar_output(timer_info: &TimerInfo, status_manager: &StatusManager) -> Result<(), String> {
+    match render_waybar_output(timer_info, status_manager) {
+        Some(outputs) => write_rendered_output(outputs),
+        None => Ok(()),
+    }
+}
+
+// The main and compact outputs for `timer_info`, or None with the integration disabled
+pub fn render_waybar_output(timer_info: &TimerInfo, status_manager: &StatusManager) -> Option<(WaybarOutput, WaybarOutput)> {
     let config = config::get();
     
     if !config.waybar_integration.enabled {
-        return Ok(());
+        return None;
     }
     
     let mut output = WaybarOutput::default();
+    let mut text_color = None;
+    let mut mini_text = None;
     
     match timer_info.state {
         TimerState::Idle => {
-            output.text = "🍅 Idle".to_string();
-            output.class = Some("idle".to_string());
-            output.tooltip = Some("Tomato Clock is idle".to_string());
+            // Hint at what `start` will use, falling back to the plain idle text
+            let default_status = if config.waybar_integration.idle_show_default_status {
+                status_manager.get_status(&config.default_status)
+            } else {
+                None
+            };
+            
+            if let Some(status) = default_status {
+                output.text = format!("{} (Idle)", status);
+                output.tooltip = Some(format!("Tomato Clock is idle\nNext start: {}", status.name));
+            } else {
+                output.text = "🍅 Idle".to_string();
+                output.tooltip = Some("Tomato Clock is idle".to_string());
+            }
+            output.class = vec!["idle".to_string()];
         },
         TimerState::Running => {
             if let (Some(phase), Some(status)) = (&timer_info.current_phase, &timer_info.current_status) {
-                let icon = phase.icon.clone().unwrap_or_else(|| "🍅".to_string());
+                let workflow_icon = timer_info.current_workflow.as_ref().and_then(|w| w.icon.as_deref());
+                let icon = phase.icon.as_deref().or(workflow_icon).unwrap_or("🍅").to_string();
                 let status_name = &status.name;
                 
                 // Get time remaining or calculate it
-                let time_str = if let Some(time_remaining) = timer_info.time_remaining {
-                    format_time_remaining(time_remaining)
-                } else {
-                    // Calculate from phase duration and elapsed time
-                    let total_duration = Duration::minutes(phase.duration as i64);
-                    let remaining = if total_duration > timer_info.elapsed_time {
-                        total_duration - timer_info.elapsed_time
-                    } else {
-                        Duration::zero()
-                    };
-                    format_time_remaining(remaining)
-                };
+                let remaining = remaining_in_phase(timer_info, phase);
+                let time_str = phase_clock(timer_info, phase, remaining, config.waybar_integration.compact_minutes);
+                let reps = reps_left(timer_info);
+                
+                // Calculate percentage for progress bar
+                let percentage = current_phase_percentage(timer_info, phase);
+                let progress_bar = render_progress_bar(percentage, config.waybar_integration.progress_bar_width);
+                
+                // Flips every second the phase runs, so a stalled daemon shows as a frozen glyph
+                let [beat, rest] = &config.waybar_integration.heartbeat_glyphs;
+                let heartbeat = if timer_info.elapsed_time.num_seconds() % 2 == 0 { beat } else { rest };
                 
                 // Format according to config
-                let text = config.waybar_integration.format.clone()
-                    .replace("{icon}", &icon)
-                    .replace("{status}", status_name)
-                    .replace("{remaining}", &time_str)
-                    .replace("{phase}", &phase.name);
+                let render = |format: &str| {
+                    format
+                        .replace("{icon}", &icon)
+                        .replace("{status}", status_name)
+                        .replace("{remaining}", &time_str)
+                        .replace("{phase}", &phase.name)
+                        .replace("{cycle}", &timer_info.cycle_count.to_string())
+                        .replace("{progress_bar}", &progress_bar)
+                        .replace("{heartbeat}", heartbeat)
+                        .replace("{reps}", &reps)
+                        .replace("{workflow}", workflow_name(timer_info))
+                };
                 
-                output.text = text;
-                output.tooltip = Some(format!(
-                    "{}: {} ({})\nRemaining: {}\nElapsed: {}",
-                    status_name,
-                    phase.name,
-                    phase.description.clone().unwrap_or_else(|| "".to_string()),
-                    time_str,
-                    format_time_remaining(timer_info.elapsed_time)
-                ));
+                output.text = render(&active_format(&config.waybar_integration.format));
+                mini_text = Some(render(&config.waybar_integration.mini_format));
+                output.tooltip = match &config.waybar_integration.tooltip_format {
+                    Some(template) => render_tooltip(template, timer_info, phase, status_name, remaining),
+                    None => Some(format!(
+                        "{}: {} ({})\n{}\nElapsed: {}\nSession: {}",
+                        status_name,
+                        phase.name,
+                        phase.description.clone().unwrap_or_else(|| "".to_string()),
+                        match timer_info.reps_remaining {
+                            Some(reps) => format!("Reps left: {}", reps),
+                            None => format!("Remaining: {}", format_time_remaining(remaining)),
+                        },
+                        format_time_remaining(timer_info.elapsed_time),
+                        format_time_remaining(timer_info.session_elapsed)
+                    )),
+                };
+                if let (Some(tooltip), Some(at)) = (output.tooltip.as_mut(), schedule::pause_at()) {
+                    tooltip.push_str(&format!("\nWill pause at {}", at.format("%H:%M")));
+                }
                 
-                // Calculate percentage for progress bar
-                let total_duration = Duration::minutes(phase.duration as i64);
-                let percentage = if total_duration.num_seconds() > 0 {
-                    let elapsed = if let Some(time_remaining) = timer_info.time_remaining {
-                        total_duration - time_remaining
-                    } else {
-                        timer_info.elapsed_time.min(total_duration)
-                    };
-                    let percent = (elapsed.num_seconds() * 100) / total_duration.num_seconds();
-                    Some(percent.min(100) as u8)
+                // A draining ring counts down like the timer does
+                output.percentage = if config.waybar_integration.percentage_inverts {
+                    percentage.map(|p| 100 - p.min(100))
                 } else {
-                    None
+                    percentage
                 };
+                output.class = vec!["running".to_string()];
                 
-                output.percentage = percentage;
-                output.class = Some("running".to_string());
+                // Let the bar style work and break phases differently at a glance
+                if config.waybar_integration.phase_kind_classes {
+                    let kind_class = if phase.is_break() { "break" } else { "focus" };
+                    output.class.push(kind_class.to_string());
+                }
                 
                 // Add color from phase if available
                 if let Some(color) = &phase.color {
                     output.alt_text = Some(color.clone());
+                    
+                    if config.waybar_integration.phase_color_text {
+                        text_color = Some(color.clone());
+                    }
+                }
+                
+                // Runs every update, so a malformed color just falls back instead of logging
+                if config.waybar_integration.progress_gradient {
+                    let waybar = &config.waybar_integration;
+                    let gradient = gradient_color(&waybar.gradient_start_color, &waybar.gradient_end_color, percentage.unwrap_or(0));
+                    text_color = gradient.or(text_color);
                 }
             } else {
                 output.text = "🍅 Running".to_string();
-                output.class = Some("running".to_string());
+                output.class = vec!["running".to_string()];
             }
         },
         TimerState::Paused => {
             if let (Some(phase), Some(status)) = (&timer_info.current_phase, &timer_info.current_status) {
-                let icon = phase.icon.clone().unwrap_or_else(|| "⏸️".to_string());
+                let workflow_icon = timer_info.current_workflow.as_ref().and_then(|w| w.icon.as_deref());
+                let icon = phase.icon.as_deref().or(workflow_icon).unwrap_or("⏸️").to_string();
                 let status_name = &status.name;
+                let remaining = remaining_in_phase(timer_info, phase);
+                let progress_bar = render_progress_bar(
+                    current_phase_percentage(timer_info, phase),
+                    config.waybar_integration.progress_bar_width,
+                );
                 
                 output.text = format!("{} {} (Paused)", icon, status_name);
-                output.tooltip = Some(format!(
-                    "{}: {} (Paused)\nElapsed: {}",
-                    status_name,
-                    phase.name,
-                    format_time_remaining(timer_info.elapsed_time)
-                ));
-                output.class = Some("paused".to_string());
+                mini_text = Some(
+                    config.waybar_integration.mini_format
+                        .replace("{icon}", "⏸️")
+                        .replace("{status}", status_name)
+                        .replace("{remaining}", &phase_clock(timer_info, phase, remaining, config.waybar_integration.compact_minutes))
+                        .replace("{phase}", &phase.name)
+                        .replace("{cycle}", &timer_info.cycle_count.to_string())
+                        .replace("{progress_bar}", &progress_bar)
+                        .replace("{heartbeat}", &config.waybar_integration.heartbeat_glyphs[1])
+                        .replace("{reps}", &reps_left(timer_info))
+                        .replace("{workflow}", workflow_name(timer_info))
+                );
+                output.tooltip = match &config.waybar_integration.tooltip_format {
+                    Some(template) => render_tooltip(template, timer_info, phase, status_name, remaining),
+                    None => Some(format!(
+                        "{}: {} (Paused)\nElapsed: {}\nSession: {}",
+                        status_name,
+                        phase.name,
+                        format_time_remaining(timer_info.elapsed_time),
+                        format_time_remaining(timer_info.session_elapsed)
+                    )),
+                };
+                output.class = vec!["paused".to_string()];
             } else {
                 output.text = "🍅 Paused".to_string();
-                output.class = Some("paused".to_string());
+                output.class = vec!["paused".to_string()];
             }
         },
+        TimerState::WaitingForStart => {
+            output.text = "⏭️ Ready — press start".to_string();
+            output.class = vec!["waiting".to_string()];
+            output.tooltip = Some(match &timer_info.current_phase {
+                Some(phase) => format!("Next: {}\nRun `tomato-clock start` to begin", phase),
+                None => "Run `tomato-clock start` to begin".to_string(),
+            });
+        },
         TimerState::Completed => {
             output.text = "🍅 Completed".to_string();
-            output.class = Some("completed".to_string());
+            output.class = vec!["completed".to_string()];
             output.tooltip = Some("Tomato Clock cycle completed".to_string());
         }
     }
     
-    // Update global output
-    *WAYBAR_OUTPUT.lock().unwrap() = output.clone();
+    // The compact output shares everything but the text; outside a phase it's just the icon
+    let mut mini_output = output.clone();
+    mini_output.text = mini_text.unwrap_or_else(|| {
+        output.text.split_whitespace().next().unwrap_or("🍅").to_string()
+    });
     
-    // Write to file for Waybar
-    write_waybar_output(&output)
+    if let Some(min_width) = config.waybar_integration.min_width {
+        output.text = pad_to_width(&output.text, min_width);
+    }
+    
+    // Wrap after padding so the markup doesn't count towards the width
+    if let Some(color) = text_color {
+        output.text = format!("<span color=\"{}\">{}</span>", color, escape_markup(&output.text));
+        mini_output.text = format!("<span color=\"{}\">{}</span>", color, escape_markup(&mini_output.text));
+    }
+    
+    Some((output, mini_output))
 }
 
-fn write_waybar_output(output: &WaybarOutput) -> Result<(), String> {
-    let output_path = get_waybar_output_path();
+// Write outputs from `render_waybar_output`, skipping files whose content is unchanged
+pub fn write_rendered_output((output, mini_output): (WaybarOutput, WaybarOutput)) -> Result<(), String> {
+    if let Some(mini_path) = get_mini_output_path() {
+        write_if_changed(&MINI_OUTPUT, mini_output, &mini_path)?;
+    }
+    
+    write_if_changed(&WAYBAR_OUTPUT, output, &get_waybar_output_path())
+}
+
+fn write_if_changed(cache: &Mutex<WaybarOutput>, output: WaybarOutput, output_path: &Path) -> Result<(), String> {
+    // Skip the write when nothing changed since the last successful one
+    if *lock_or_recover(cache) == output && output_path.exists() {
+        return Ok(());
+    }
     
+    // Write to file for Waybar
+    write_waybar_output(&output, output_path)?;
+    
+    // Update global output only once it's actually on disk
+    *lock_or_recover(cache) = output;
+    
+    Ok(())
+}
+
+fn write_waybar_output(output: &WaybarOutput, output_path: &Path) -> Result<(), String> {
+
     // Create directory if it doesn't exist
     if let Some(parent) = output_path.parent() {
         if !parent.exists() {
@@ -178,7 +552,7 @@ fn write_waybar_output(output: &WaybarOutput) -> Result<(), String> {
     let output_str = serde_json::to_string(output)
         .map_err(|e| format!("Failed to serialize waybar output: {}", e))?;
     
-    fs::write(&output_path, output_str)
+    fs::write(output_path, output_str)
         .map_err(|e| format!("Failed to write waybar output file: {}", e))?;
     
     Ok(())
@@ -204,4 +578,81 @@ pub fn process_waybar_click(button: u8) -> Result<(), String> {
         },
         _ => Ok(()),
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    
+    use crate::test_support;
+    
+    #[test]
+    fn phase_percentage_runs_from_zero_to_full_and_clamps_overtime() {
+        let total = Duration::minutes(25);
+        assert_eq!(phase_percentage(Duration::zero(), total), Some(0));
+        assert_eq!(phase_percentage(total, total), Some(100));
+        assert_eq!(phase_percentage(total + Duration::minutes(3), total), Some(100));
+    }
+    
+    #[test]
+    fn phase_percentage_is_none_for_a_zero_length_phase() {
+        assert_eq!(phase_percentage(Duration::seconds(10), Duration::zero()), None);
+    }
+    
+    #[test]
+    fn padding_counts_emoji_and_cjk_as_two_columns() {
+        assert_eq!(pad_to_width("🍅 24:59", 10), "🍅 24:59  ");
+        assert_eq!(pad_to_width("作業 24:59", 12), "作業 24:59  ");
+        assert_eq!(pad_to_width("🍅 24:59", 4), "🍅 24:59");
+    }
+    
+    #[test]
+    fn negative_and_zero_time_left_show_as_zero() {
+        assert_eq!(format_time_remaining(Duration::zero()), "00:00");
+        assert_eq!(format_time_remaining(Duration::seconds(-90)), "00:00");
+        assert_eq!(format_time_remaining(Duration::seconds(90)), "01:30");
+    }
+    
+    #[test]
+    fn compact_time_shows_minutes_until_the_last_one() {
+        assert_eq!(format_time_compact(Duration::seconds(61)), "1m");
+        assert_eq!(format_time_compact(Duration::seconds(60)), "01:00");
+        assert_eq!(format_time_compact(Duration::seconds(59)), "00:59");
+    }
+    
+    #[tokio::test]
+    async fn the_format_override_is_read_again_once_it_changes() {
+        let _sandbox = test_support::sandbox().await;
+        assert_eq!(active_format("{remaining}"), "{remaining}");
+        
+        fs::write(get_format_override_path(), "{phase}\n").unwrap();
+        assert_eq!(active_format("{remaining}"), "{phase}");
+        assert_eq!(active_format("{remaining}"), "{phase}");
+        
+        fs::write(get_format_override_path(), "{icon} {phase}").unwrap();
+        assert_eq!(active_format("{remaining}"), "{icon} {phase}");
+        
+        fs::remove_file(get_format_override_path()).unwrap();
+        assert_eq!(active_format("{remaining}"), "{remaining}");
+    }
+    
+    
+    #[tokio::test]
+    async fn paused_tooltip_has_no_end_time() {
+        let _sandbox = test_support::sandbox().await;
+        test_support::set_clock(Local.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap());
+        let phase = Phase::new("Work", 25);
+        let mut info = TimerInfo {
+            state: TimerState::Running,
+            current_phase: Some(phase.clone()),
+            time_remaining: Some(Duration::minutes(20)),
+            ..Default::default()
+        };
+        let render = |info: &TimerInfo| render_tooltip("Ends at {ends_at}", info, &phase, "work", Duration::minutes(20));
+        assert_eq!(render(&info).as_deref(), Some("Ends at 09:20"));
+        
+        info.state = TimerState::Paused;
+        assert_eq!(render(&info).as_deref(), Some("Ends at —"));
+    }
+}
\ No newline at end of file
@@ -1,10 +1,20 @@
+use log::warn;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config;
+use crate::lock::lock_or_recover;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhaseKind {
+    Work,
+    Break,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Phase {
@@ -13,6 +23,36 @@ pub struct Phase {
     pub description: Option<String>,
     pub color: Option<String>,
     pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<PhaseKind>,
+    /// Whether `skip` may end this phase early; false makes it a mandatory break
+    #[serde(default = "default_skippable", skip_serializing_if = "is_skippable")]
+    pub skippable: bool,
+    /// Start the following phase automatically; false waits for `start` when this one ends
+    #[serde(default = "default_auto_start_next", skip_serializing_if = "is_auto_start_next")]
+    pub auto_start_next: bool,
+    /// End after this many `rep`s instead of when `duration` runs out; the timer counts up meanwhile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reps: Option<u32>,
+    /// Sound file played when this phase ends, instead of the `sound_theme` one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+}
+
+fn default_auto_start_next() -> bool {
+    true
+}
+
+fn is_auto_start_next(auto_start_next: &bool) -> bool {
+    *auto_start_next
+}
+
+fn default_skippable() -> bool {
+    true
+}
+
+fn is_skippable(skippable: &bool) -> bool {
+    *skippable
 }
 
 impl Phase {
@@ -23,31 +63,102 @@ impl Phase {
             description: None,
             color: None,
             icon: None,
+            kind: None,
+            skippable: true,
+            auto_start_next: true,
+            reps: None,
+            sound: None,
         }
     }
-
+    
+    pub fn with_kind(mut self, kind: PhaseKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+    
+    /// The explicit kind, or a guess from the name for phases saved without one
+    pub fn kind(&self) -> PhaseKind {
+        self.kind.unwrap_or_else(|| {
+            // Whole words only, so "Interest review" or "Restructure" stay work
+            let name = self.name.to_lowercase();
+            if name.split(|c: char| !c.is_alphanumeric()).any(|word| word == "break" || word == "rest") {
+                PhaseKind::Break
+            } else {
+                PhaseKind::Work
+            }
+        })
+    }
+    
+    pub fn is_break(&self) -> bool {
+        self.kind() == PhaseKind::Break
+    }
+    
     pub fn with_description(mut self, description: &str) -> Self {
         self.description = Some(description.to_string());
         self
     }
-
+    
     pub fn with_color(mut self, color: &str) -> Self {
         self.color = Some(color.to_string());
         self
     }
-
+    
+    pub fn with_reps(mut self, reps: u32) -> Self {
+        self.reps = Some(reps);
+        self
+    }
+    
+    /// Check the fields the timer relies on: a name and a positive duration or rep count
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Phase name must not be empty".to_string());
+        }
+        match self.reps {
+            Some(0) => Err(format!("Phase '{}': reps must be a positive integer", self.name)),
+            Some(_) => Ok(()),
+            None => validate_duration(self.duration).map_err(|e| format!("Phase '{}': {}", self.name, e)),
+        }
+    }
+    
     pub fn with_icon(mut self, icon: &str) -> Self {
         self.icon = Some(icon.to_string());
         self
     }
 }
 
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reps {
+            Some(reps) => write!(f, "{} ({} reps)", self.name, reps),
+            None => write!(f, "{} ({}m)", self.name, self.duration),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Workflow {
     pub name: String,
     pub phases: Vec<Phase>,
     pub description: Option<String>,
     pub repeatable: bool,
+    /// Shown in `workflow list` and used for phases without an icon of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// Built-in workflows `workflow add --from-template` can start from, keyed by template id
+pub fn templates() -> Vec<(&'static str, Workflow)> {
+    vec![
+        ("default-pomodoro", Workflow::default()),
+        ("long-work", Workflow::long_work()),
+    ]
+}
+
+pub fn template(id: &str) -> Option<Workflow> {
+    templates()
+        .into_iter()
+        .find(|(template_id, _)| *template_id == id)
+        .map(|(_, workflow)| workflow)
 }
 
 impl Default for Workflow {
@@ -58,14 +169,17 @@ impl Default for Workflow {
                 Phase::new("Work", 25)
                     .with_description("Focus on work")
                     .with_color("#ff5555")
-                    .with_icon("🔨"),
+                    .with_icon("🔨")
+                    .with_kind(PhaseKind::Work),
                 Phase::new("Break", 5)
                     .with_description("Take a short break")
                     .with_color("#50fa7b")
-                    .with_icon("☕"),
+                    .with_icon("☕")
+                    .with_kind(PhaseKind::Break),
             ],
             description: Some("Standard Pomodoro technique workflow".to_string()),
             repeatable: true,
+            icon: Some("🍅".to_string()),
         }
     }
 }
@@ -77,68 +191,404 @@ impl Workflow {
             phases: Vec::new(),
             description: None,
             repeatable: true,
+            icon: None,
         }
     }
-
+    
+    fn long_work() -> Self {
+        Workflow::new("Long Work Session")
+            .with_phases(vec![
+                Phase::new("Work", 50)
+                    .with_description("Focus on work")
+                    .with_color("#ff5555")
+                    .with_icon("🔨")
+                    .with_kind(PhaseKind::Work),
+                Phase::new("Break", 10)
+                    .with_description("Take a break")
+                    .with_color("#50fa7b")
+                    .with_icon("☕")
+                    .with_kind(PhaseKind::Break),
+            ])
+            .with_description("Longer work sessions with longer breaks")
+            .with_repeatable(true)
+    }
+    
     pub fn with_phases(mut self, phases: Vec<Phase>) -> Self {
         self.phases = phases;
         self
     }
-
+    
     pub fn with_description(mut self, description: &str) -> Self {
         self.description = Some(description.to_string());
         self
     }
-
+    
+    pub fn with_icon(mut self, icon: &str) -> Self {
+        self.icon = Some(icon.to_string());
+        self
+    }
+    
     pub fn with_repeatable(mut self, repeatable: bool) -> Self {
         self.repeatable = repeatable;
         self
     }
-
+    
     #[allow(dead_code)]
     pub fn add_phase(&mut self, phase: Phase) {
         self.phases.push(phase);
     }
-
+    
+    /// Parse `name:duration,...`, where `Nx(...)` repeats a group of phases N times,
+    /// e.g. `3x(Work:25,Break:5),Work:25,LongBreak:15`. Groups can be nested.
+    ///
+    /// Advancing finds the current phase by name, so repeated names are numbered to
+    /// keep every phase reachable: Work, Break, Work 2, Break 2, ...
     pub fn parse_phases(phases_str: &str) -> Result<Vec<Phase>, &'static str> {
-        let parts = phases_str.split(',');
+        let mut phases = Self::parse_phase_list(phases_str)?;
+        number_repeated_names(&mut phases);
+        Ok(phases)
+    }
+    
+    /// Parse a phases file for `workflow add --phases-file`: one `name:duration[:icon:color]`
+    /// per line (or anything `parse_phases` accepts), with blank lines and `#` comment
+    /// lines skipped. Errors name the offending line
+    pub fn parse_phases_file(content: &str) -> Result<Vec<Phase>, String> {
         let mut phases = Vec::new();
-
-        for part in parts {
-            let phase_parts: Vec<&str> = part.trim().split(':').collect();
+        
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            let fail = |e: &str| format!("Line {}: {}", index + 1, e);
+            
+            // Groups have colons of their own, so only plain phases take an icon and color
+            let parts: Vec<&str> = line.split(':').collect();
+            let (spec, icon, color) = if !line.contains('(') && parts.len() > 2 {
+                if parts.len() > 4 {
+                    return Err(fail("Invalid phase format, use 'name:duration[:icon:color]'"));
+                }
+                (parts[..2].join(":"), Some(parts[2].trim()), parts.get(3).map(|color| color.trim()))
+            } else {
+                (line.to_string(), None, None)
+            };
+            
+            for mut phase in Self::parse_phase_list(&spec).map_err(fail)? {
+                if let Some(icon) = icon.filter(|icon| !icon.is_empty()) {
+                    phase = phase.with_icon(icon);
+                }
+                if let Some(color) = color.filter(|color| !color.is_empty()) {
+                    phase = phase.with_color(color);
+                }
+                phases.push(phase);
+            }
+        }
+        
+        if phases.is_empty() {
+            return Err("No phases provided".to_string());
+        }
+        
+        number_repeated_names(&mut phases);
+        Ok(phases)
+    }
+    
+    fn parse_phase_list(phases_str: &str) -> Result<Vec<Phase>, &'static str> {
+        let mut phases = Vec::new();
+        
+        for part in split_top_level(phases_str)? {
+            let part = part.trim();
+            
+            if let Some((count, group)) = part.split_once("x(") {
+                let Some(group) = group.strip_suffix(')') else {
+                    return Err("Invalid repetition, use 'Nx(name:duration,...)'");
+                };
+                let count = match count.trim().parse::<u32>() {
+                    Ok(count) if count > 0 => count,
+                    _ => return Err("Invalid repetition count, must be a positive integer"),
+                };
+                
+                let group_phases = Self::parse_phase_list(group)?;
+                for _ in 0..count {
+                    phases.extend(group_phases.iter().cloned());
+                }
+                continue;
+            }
+            
+            let phase_parts: Vec<&str> = part.split(':').collect();
             if phase_parts.len() != 2 {
                 return Err("Invalid phase format, use 'name:duration'");
             }
-
+            
             let name = phase_parts[0].trim();
+            
+            // `name:15reps` is advanced by `rep` rather than the clock
+            if let Some(reps) = phase_parts[1].trim().strip_suffix("reps") {
+                match reps.trim().parse::<u32>() {
+                    Ok(reps) if reps > 0 => phases.push(Phase::new(name, 0).with_reps(reps)),
+                    _ => return Err("Invalid reps, must be a positive integer"),
+                }
+                continue;
+            }
+            
             let duration = match phase_parts[1].trim().parse::<u32>() {
                 Ok(duration) => duration,
                 Err(_) => return Err("Invalid duration, must be a positive integer"),
             };
-
+            validate_duration(duration)?;
+            
             phases.push(Phase::new(name, duration));
         }
-
+        
         if phases.is_empty() {
             return Err("No phases provided");
         }
-
+        
         Ok(phases)
     }
+    
+    // Indices the timer can actually reach. Advancing looks the current phase up
+    // by name, so a repeated name sends the timer back to its first occurrence.
+    fn reachable_phases(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.phases.len()];
+        let mut index = 0;
+        
+        while index < self.phases.len() && !reached[index] {
+            reached[index] = true;
+            
+            let name = &self.phases[index].name;
+            let position = self.phases.iter().position(|p| &p.name == name).unwrap_or(index);
+            
+            if position + 1 < self.phases.len() {
+                index = position + 1;
+            } else if self.repeatable {
+                index = 0;
+            } else {
+                break;
+            }
+        }
+        
+        reached
+    }
+    
+    /// Reject workflows that can't run at all: no name, no phases or a broken phase.
+    /// Softer problems are left to `validation_warnings`
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Workflow name must not be empty".to_string());
+        }
+        if self.phases.is_empty() {
+            return Err(format!("Workflow '{}' has no phases", self.name));
+        }
+        
+        for phase in &self.phases {
+            phase.validate().map_err(|e| format!("Workflow '{}': {}", self.name, e))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Problems that make a workflow misbehave at runtime, as human-readable warnings
+    pub fn validation_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        
+        if self.phases.is_empty() {
+            warnings.push("Workflow has no phases, starting it does nothing".to_string());
+            return warnings;
+        }
+        
+        for phase in &self.phases {
+            if let Err(e) = phase.validate() {
+                warnings.push(e);
+            }
+        }
+        
+        let mut seen_names: Vec<&str> = Vec::new();
+        for phase in &self.phases {
+            if seen_names.contains(&phase.name.as_str()) {
+                warnings.push(format!("Phase name '{}' is used more than once, rename the copies", phase.name));
+            } else {
+                seen_names.push(&phase.name);
+            }
+        }
+        
+        for (index, reached) in self.reachable_phases().into_iter().enumerate() {
+            if !reached {
+                warnings.push(format!(
+                    "Phase {} ('{}') is never reached because of a duplicate phase name",
+                    index + 1,
+                    self.phases[index].name
+                ));
+            }
+        }
+        
+        // Rep-based phases take as long as they take
+        let rep_based = self.phases.iter().any(|p| p.reps.is_some());
+        let total_minutes: u32 = self.phases.iter().map(|p| p.duration).sum();
+        if !self.repeatable && !rep_based && total_minutes < SHORT_WORKFLOW_MINUTES {
+            warnings.push(format!(
+                "Workflow is not repeatable and completes after only {} minutes",
+                total_minutes
+            ));
+        }
+        
+        warnings
+    }
+    
+    /// Minutes of the phases between `current_index` and the next break phase,
+    /// following a repeatable workflow around; `None` when no break comes up
+    pub fn minutes_until_next_break(&self, current_index: usize) -> Option<u32> {
+        let len = self.phases.len();
+        let mut minutes = 0;
+        
+        for step in 1..=len {
+            let index = current_index + step;
+            if index >= len && !self.repeatable {
+                return None;
+            }
+            
+            let phase = &self.phases[index % len];
+            if phase.is_break() {
+                return Some(minutes);
+            }
+            minutes += phase.duration;
+        }
+        
+        None
+    }
+}
+
+// Advancing finds the current phase by name, so number repeats: Work, Break, Work 2, ...
+// The first phase of each name keeps it, and numbers a written name uses are skipped
+fn number_repeated_names(phases: &mut [Phase]) {
+    let mut taken: HashSet<String> = phases.iter().map(|phase| phase.name.clone()).collect();
+    let mut seen = HashSet::new();
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    
+    for phase in phases {
+        if seen.insert(phase.name.clone()) {
+            continue;
+        }
+        
+        let count = counts.entry(phase.name.clone()).or_insert(1);
+        let name = loop {
+            *count += 1;
+            let name = format!("{} {}", phase.name, count);
+            if !taken.contains(&name) {
+                break name;
+            }
+        };
+        taken.insert(name.clone());
+        phase.name = name;
+    }
+}
+
+// Split on commas outside parentheses, so `2x(A:1,B:1),C:1` gives `2x(A:1,B:1)` and `C:1`
+fn split_top_level(phases_str: &str) -> Result<Vec<&str>, &'static str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    
+    for (index, c) in phases_str.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1).ok_or("Unbalanced parentheses in phases")?,
+            ',' if depth == 0 => {
+                parts.push(&phases_str[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    
+    if depth != 0 {
+        return Err("Unbalanced parentheses in phases");
+    }
+    parts.push(&phases_str[start..]);
+    
+    Ok(parts)
+}
+
+/// Non-repeatable workflows shorter than this are flagged by `validate`
+const SHORT_WORKFLOW_MINUTES: u32 = 5;
+
+pub fn validate_duration(duration: u32) -> Result<(), &'static str> {
+    if duration == 0 {
+        return Err("Invalid duration, must be a positive integer");
+    }
+    Ok(())
+}
+
+/// Why a `WorkflowManager` operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum WorkflowError {
+    #[error("Workflow '{0}' not found")]
+    NotFound(String),
+    #[error("Workflow '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("Workflow '{0}' is defined in workflows.d, delete its file to remove it")]
+    ReadOnly(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{context}: {source}")]
+    Io {
+        context: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{context}: {message}")]
+    Parse {
+        context: &'static str,
+        message: String,
+    },
+}
+
+impl WorkflowError {
+    fn io(context: &'static str) -> impl FnOnce(std::io::Error) -> Self {
+        move |source| Self::Io { context, source }
+    }
+    
+    fn parse<E: fmt::Display>(context: &'static str) -> impl FnOnce(E) -> Self {
+        move |e| Self::Parse { context, message: e.to_string() }
+    }
+    
+    /// Process exit code for the CLI, following the BSD sysexits convention
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => 66,      // EX_NOINPUT
+            Self::AlreadyExists(_) => 73, // EX_CANTCREAT
+            Self::ReadOnly(_) => 77,      // EX_NOPERM
+            Self::Invalid(_) | Self::Parse { .. } => 65, // EX_DATAERR
+            Self::Io { .. } => 74,        // EX_IOERR
+        }
+    }
 }
 
+/// Outcome of a batch import: what got in and what was skipped, and why
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub skipped: Vec<(String, String)>,
+}
+
+type WorkflowMap = HashMap<String, Workflow>;
+
 #[derive(Debug)]
 pub struct WorkflowManager {
     workflows: Arc<Mutex<HashMap<String, Workflow>>>,
+    // Workflows dropped into workflows.d; read-only and never written back to the JSON file
+    directory_workflows: Arc<Mutex<HashMap<String, Workflow>>>,
+    // Entries of workflows.json that fail validation: never offered or run, but written
+    // back as they were so saving another change doesn't delete them
+    invalid_workflows: Arc<Mutex<HashMap<String, Workflow>>>,
     workflow_file: PathBuf,
 }
 
 impl WorkflowManager {
     pub fn new() -> Self {
-        let mut workflow_file = config::get_config_dir();
-        workflow_file.push("workflows.json");
+        let workflow_file = Self::get_workflow_file();
         
-        let workflows = Self::load_workflows(&workflow_file).unwrap_or_else(|_| {
+        let (workflows, invalid_workflows) = Self::load_workflows(&workflow_file).unwrap_or_else(|_| {
             let mut default_workflows = HashMap::new();
             
             // Add default workflows
@@ -149,119 +599,371 @@ impl WorkflowManager {
             
             default_workflows.insert(
                 "Long Work Session".to_string(),
-                Workflow::new("Long Work Session")
-                    .with_phases(vec![
-                        Phase::new("Work", 50)
-                            .with_description("Focus on work")
-                            .with_color("#ff5555")
-                            .with_icon("🔨"),
-                        Phase::new("Break", 10)
-                            .with_description("Take a break")
-                            .with_color("#50fa7b")
-                            .with_icon("☕"),
-                    ])
-                    .with_description("Longer work sessions with longer breaks")
-                    .with_repeatable(true),
+                Workflow::long_work(),
             );
             
-            default_workflows
+            (default_workflows, HashMap::new())
         });
         
+        let directory_workflows = Self::load_workflow_dir(&Self::get_workflow_dir());
+        
         Self {
             workflows: Arc::new(Mutex::new(workflows)),
+            directory_workflows: Arc::new(Mutex::new(directory_workflows)),
+            invalid_workflows: Arc::new(Mutex::new(invalid_workflows)),
             workflow_file,
         }
     }
     
-    fn load_workflows(file_path: &PathBuf) -> Result<HashMap<String, Workflow>, String> {
-        if !file_path.exists() {
-            return Err("Workflow file does not exist".to_string());
+    pub fn get_workflow_file() -> PathBuf {
+        let mut workflow_file = config::get_config_dir();
+        workflow_file.push("workflows.json");
+        workflow_file
+    }
+    
+    pub fn get_workflow_dir() -> PathBuf {
+        let mut workflow_dir = config::get_config_dir();
+        workflow_dir.push("workflows.d");
+        workflow_dir
+    }
+    
+    // Load every `*.toml` file in the directory as a single workflow, skipping bad files
+    fn load_workflow_dir(dir_path: &Path) -> HashMap<String, Workflow> {
+        let mut workflows = HashMap::new();
+        
+        let entries = match fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(_) => return workflows,
+        };
+        
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+        
+        for path in paths {
+            let loaded = Self::load_workflow_toml(&path).and_then(|workflow| {
+                workflow.validate().map_err(WorkflowError::Invalid)?;
+                Ok(workflow)
+            });
+            
+            match loaded {
+                Ok(workflow) => {
+                    workflows.insert(workflow.name.clone(), workflow);
+                }
+                Err(e) => warn!("Skipping workflow file {}: {}", path.display(), e),
+            }
         }
         
+        workflows
+    }
+    
+    fn load_workflow_toml(file_path: &Path) -> Result<Workflow, WorkflowError> {
+        let file_content = fs::read_to_string(file_path)
+            .map_err(WorkflowError::io("Failed to read workflow file"))?;
+        
+        toml::from_str(&file_content)
+            .map_err(WorkflowError::parse("Failed to parse workflow file"))
+    }
+    
+    // A JSON file may hold a single workflow, a name -> workflow map, or a list
+    fn load_workflows_json_any(file_path: &Path) -> Result<Vec<Workflow>, WorkflowError> {
         let file_content = fs::read_to_string(file_path)
-            .map_err(|e| format!("Failed to read workflow file: {}", e))?;
+            .map_err(WorkflowError::io("Failed to read workflow file"))?;
         
-        serde_json::from_str(&file_content)
-            .map_err(|e| format!("Failed to parse workflow file: {}", e))
+        if file_content.trim_start().starts_with('[') {
+            return serde_json::from_str::<Vec<Workflow>>(&file_content)
+                .map_err(WorkflowError::parse("Failed to parse workflow file"));
+        }
+        
+        match serde_json::from_str::<Workflow>(&file_content) {
+            Ok(workflow) => Ok(vec![workflow]),
+            Err(e) => serde_json::from_str::<HashMap<String, Workflow>>(&file_content)
+                .map(|workflows| workflows.into_values().collect())
+                .map_err(|_| WorkflowError::parse("Failed to parse workflow file")(e)),
+        }
     }
     
-    fn save_workflows(&self) -> Result<(), String> {
-        let workflows = self.workflows.lock().unwrap();
+    /// Import every `*.toml` and `*.json` file in a directory into workflows.json.
+    /// A bad file or duplicate name is recorded in the report and never stops the rest.
+    pub fn import_dir(&self, dir_path: &Path) -> Result<ImportReport, WorkflowError> {
+        let entries = fs::read_dir(dir_path)
+            .map_err(WorkflowError::io("Failed to read import directory"))?;
+        
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        paths.sort();
+        
+        let mut report = ImportReport::default();
+        
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string());
+            
+            let loaded = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("toml") => Self::load_workflow_toml(&path).map(|workflow| vec![workflow]),
+                Some("json") => Self::load_workflows_json_any(&path),
+                _ => continue,
+            };
+            
+            let workflows = match loaded {
+                Ok(workflows) => workflows,
+                Err(e) => {
+                    report.skipped.push((file_name, e.to_string()));
+                    continue;
+                }
+            };
+            
+            let multiple = workflows.len() > 1;
+            for workflow in workflows {
+                let name = workflow.name.clone();
+                match self.add_workflow(workflow) {
+                    Ok(_) => report.imported.push(name),
+                    Err(e) => {
+                        let label = if multiple {
+                            format!("{} ({})", file_name, name)
+                        } else {
+                            file_name.clone()
+                        };
+                        report.skipped.push((label, e.to_string()));
+                    }
+                }
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Write a single workflow as TOML, e.g. into workflows.d
+    pub fn write_workflow_file(workflow: &Workflow, file_path: &Path) -> Result<(), WorkflowError> {
+        if let Some(parent) = file_path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)
+                    .map_err(WorkflowError::io("Failed to create workflow directory"))?;
+            }
+        }
+        
+        let toml_str = toml::to_string_pretty(workflow)
+            .map_err(WorkflowError::parse("Failed to serialize workflow"))?;
+        
+        fs::write(file_path, toml_str)
+            .map_err(WorkflowError::io("Failed to write workflow file"))
+    }
+    
+    // Split into the valid workflows and the invalid ones, which a hand-edited file
+    // can hold but the timer can't run
+    fn load_workflows(file_path: &PathBuf) -> Result<(WorkflowMap, WorkflowMap), WorkflowError> {
+        let file_content = fs::read_to_string(file_path)
+            .map_err(WorkflowError::io("Failed to read workflow file"))?;
+        
+        let workflows: HashMap<String, Workflow> = serde_json::from_str(&file_content)
+            .map_err(WorkflowError::parse("Failed to parse workflow file"))?;
+        
+        Ok(workflows.into_iter().partition(|(_, workflow)| match workflow.validate() {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Skipping invalid workflow in {}: {}", file_path.display(), e);
+                false
+            }
+        }))
+    }
+    
+    fn save_workflows(&self) -> Result<(), WorkflowError> {
+        // Invalid entries go back in untouched, for the user to fix
+        let mut workflows = lock_or_recover(&self.invalid_workflows).clone();
+        workflows.extend(lock_or_recover(&self.workflows).clone());
         
         // Create directory if it doesn't exist
         if let Some(parent) = self.workflow_file.parent() {
             if !parent.exists() {
                 fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create workflows directory: {}", e))?;
+                    .map_err(WorkflowError::io("Failed to create workflows directory"))?;
             }
         }
         
-        let json = serde_json::to_string_pretty(&*workflows)
-            .map_err(|e| format!("Failed to serialize workflows: {}", e))?;
+        let json = serde_json::to_string_pretty(&workflows)
+            .map_err(WorkflowError::parse("Failed to serialize workflows"))?;
         
         fs::write(&self.workflow_file, json)
-            .map_err(|e| format!("Failed to save workflows: {}", e))
+            .map_err(WorkflowError::io("Failed to save workflows"))
     }
     
-    pub fn add_workflow(&self, workflow: Workflow) -> Result<(), &'static str> {
-        let mut workflows = self.workflows.lock().unwrap();
+    pub fn add_workflow(&self, workflow: Workflow) -> Result<(), WorkflowError> {
+        if lock_or_recover(&self.directory_workflows).contains_key(&workflow.name)
+            || lock_or_recover(&self.invalid_workflows).contains_key(&workflow.name)
+        {
+            return Err(WorkflowError::AlreadyExists(workflow.name));
+        }
+        
+        let mut workflows = lock_or_recover(&self.workflows);
         if workflows.contains_key(&workflow.name) {
-            return Err("Workflow with this name already exists");
+            return Err(WorkflowError::AlreadyExists(workflow.name));
         }
         
         workflows.insert(workflow.name.clone(), workflow);
         drop(workflows); // Release the lock before saving
         
         // Save changes to file
-        if let Err(e) = self.save_workflows() {
-            eprintln!("Failed to save workflows: {}", e);
-        }
-        
-        Ok(())
+        self.save_workflows()
     }
     
     pub fn get_workflow(&self, name: &str) -> Option<Workflow> {
-        let workflows = self.workflows.lock().unwrap();
+        // Files in workflows.d take precedence over workflows.json
+        if let Some(workflow) = lock_or_recover(&self.directory_workflows).get(name) {
+            return Some(workflow.clone());
+        }
+        
+        let workflows = lock_or_recover(&self.workflows);
         workflows.get(name).cloned()
     }
     
-    pub fn remove_workflow(&self, name: &str) -> Result<(), &'static str> {
-        let mut workflows = self.workflows.lock().unwrap();
-        if !workflows.contains_key(name) {
-            return Err("Workflow with this name does not exist");
+    /// Whether `remove_workflow` would succeed for `name`, without removing anything
+    pub fn check_removable(&self, name: &str) -> Result<(), WorkflowError> {
+        if lock_or_recover(&self.directory_workflows).contains_key(name) {
+            return Err(WorkflowError::ReadOnly(name.to_string()));
         }
         
-        workflows.remove(name);
-        drop(workflows); // Release the lock before saving
-        
-        // Save changes to file
-        if let Err(e) = self.save_workflows() {
-            eprintln!("Failed to save workflows: {}", e);
+        // Invalid entries can't be run, but can be removed
+        if !lock_or_recover(&self.workflows).contains_key(name)
+            && !lock_or_recover(&self.invalid_workflows).contains_key(name)
+        {
+            return Err(WorkflowError::NotFound(name.to_string()));
         }
         
         Ok(())
     }
     
+    pub fn remove_workflow(&self, name: &str) -> Result<(), WorkflowError> {
+        self.check_removable(name)?;
+        
+        lock_or_recover(&self.workflows).remove(name);
+        lock_or_recover(&self.invalid_workflows).remove(name);
+        
+        // Save changes to file
+        self.save_workflows()
+    }
+    
     pub fn list_workflows(&self) -> Vec<Workflow> {
-        let workflows = self.workflows.lock().unwrap();
-        workflows.values().cloned().collect()
+        let mut workflows = lock_or_recover(&self.workflows).clone();
+        
+        // Files in workflows.d take precedence over workflows.json
+        for (name, workflow) in lock_or_recover(&self.directory_workflows).iter() {
+            workflows.insert(name.clone(), workflow.clone());
+        }
+        
+        workflows.into_values().collect()
     }
     
     #[allow(dead_code)]
-    pub fn update_workflow(&self, workflow: Workflow) -> Result<(), &'static str> {
-        let mut workflows = self.workflows.lock().unwrap();
+    pub fn update_workflow(&self, workflow: Workflow) -> Result<(), WorkflowError> {
+        let mut workflows = lock_or_recover(&self.workflows);
         if !workflows.contains_key(&workflow.name) {
-            return Err("Workflow with this name does not exist");
+            return Err(WorkflowError::NotFound(workflow.name));
         }
         
         workflows.insert(workflow.name.clone(), workflow);
         drop(workflows); // Release the lock before saving
         
         // Save changes to file
-        if let Err(e) = self.save_workflows() {
-            eprintln!("Failed to save workflows: {}", e);
-        }
+        self.save_workflows()
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    use crate::test_support;
+    
+    #[tokio::test]
+    async fn invalid_workflows_are_hidden_but_kept_on_save() {
+        let _sandbox = test_support::sandbox().await;
+        let mut broken = serde_json::to_value(Workflow::new("Broken").with_phases(vec![Phase::new("Work", 25)])).unwrap();
+        broken["phases"][0]["duration"] = json!(0);
+        let valid = serde_json::to_value(Workflow::default()).unwrap();
+        let file = WorkflowManager::get_workflow_file();
+        fs::write(&file, json!({ "Broken": broken, "Default Pomodoro": valid }).to_string()).unwrap();
         
-        Ok(())
+        let manager = WorkflowManager::new();
+        assert!(manager.get_workflow("Broken").is_none());
+        assert_eq!(manager.list_workflows().len(), 1);
+        assert!(matches!(manager.add_workflow(Workflow::new("Broken")), Err(WorkflowError::AlreadyExists(_))));
+        
+        manager.add_workflow(Workflow::new("Deep Work").with_phases(vec![Phase::new("Focus", 50)])).unwrap();
+        let saved: HashMap<String, serde_json::Value> = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(saved.get("Broken"), Some(&broken));
+        assert_eq!(saved.len(), 3);
+        
+        manager.remove_workflow("Broken").unwrap();
+        let saved: HashMap<String, serde_json::Value> = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert!(!saved.contains_key("Broken"));
+    }
+    
+    #[test]
+    fn phase_displays_duration_or_reps() {
+        assert_eq!(Phase::new("Work", 25).to_string(), "Work (25m)");
+        assert_eq!(Phase::new("Push-ups", 1).with_reps(20).to_string(), "Push-ups (20 reps)");
+    }
+    
+    #[test]
+    fn a_workflow_without_phases_is_invalid() {
+        assert_eq!(Workflow::new("Empty").validate(), Err("Workflow 'Empty' has no phases".to_string()));
+        assert!(!Workflow::new("Empty").validation_warnings().is_empty());
+    }
+    
+    #[test]
+    fn negative_and_zero_durations_are_rejected() {
+        assert!(validate_duration(0).is_err());
+        assert!(validate_duration(1).is_ok());
+        assert_eq!(Workflow::parse_phases("Work:0"), Err("Invalid duration, must be a positive integer"));
+        assert_eq!(Workflow::parse_phases("Work:-5"), Err("Invalid duration, must be a positive integer"));
+    }
+    
+    fn names(phases: &[Phase]) -> Vec<&str> {
+        phases.iter().map(|phase| phase.name.as_str()).collect()
+    }
+    
+    #[test]
+    fn group_copies_are_numbered_around_names_already_taken() {
+        let phases = Workflow::parse_phases("2x(Work:25,Break:5),Work 2:10").unwrap();
+        assert_eq!(names(&phases), ["Work", "Break", "Work 3", "Break 2", "Work 2"]);
+        
+        let phases = Workflow::parse_phases("2x(Work:25,Break:5),Work:25").unwrap();
+        assert_eq!(names(&phases), ["Work", "Break", "Work 2", "Break 2", "Work 3"]);
+    }
+    
+    #[test]
+    fn nested_groups_are_expanded_and_numbered() {
+        let phases = Workflow::parse_phases("2x(2x(Work:25,Break:5),LongBreak:15)").unwrap();
+        assert_eq!(
+            names(&phases),
+            ["Work", "Break", "Work 2", "Break 2", "LongBreak", "Work 3", "Break 3", "Work 4", "Break 4", "LongBreak 2"]
+        );
+    }
+    
+    #[test]
+    fn malformed_groups_are_rejected() {
+        assert_eq!(Workflow::parse_phases("2x(Work:25"), Err("Unbalanced parentheses in phases"));
+        assert_eq!(Workflow::parse_phases("Work:25)"), Err("Unbalanced parentheses in phases"));
+        assert_eq!(Workflow::parse_phases("0x(Work:25)"), Err("Invalid repetition count, must be a positive integer"));
+        assert_eq!(Workflow::parse_phases("2x(Work:25)Break:5"), Err("Invalid repetition, use 'Nx(name:duration,...)'"));
+        assert_eq!(Workflow::parse_phases("2x()"), Err("Invalid phase format, use 'name:duration'"));
+    }
+    
+    
+    #[test]
+    fn kind_guess_matches_whole_words_only() {
+        assert_eq!(Phase::new("Interest review", 25).kind(), PhaseKind::Work);
+        assert_eq!(Phase::new("Restructure", 25).kind(), PhaseKind::Work);
+        assert_eq!(Phase::new("Forest", 25).kind(), PhaseKind::Work);
+        assert_eq!(Phase::new("Short Break", 5).kind(), PhaseKind::Break);
+        assert_eq!(Phase::new("Rest-2", 5).kind(), PhaseKind::Break);
+        assert_eq!(Phase::new("Forest", 25).with_kind(PhaseKind::Break).kind(), PhaseKind::Break);
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file